@@ -0,0 +1,150 @@
+use crate::deadlock::dead_squares;
+use crate::level::Level;
+use crate::solver::{solve, Metric, Solution, SolveError, SolverLimits};
+
+/// Metrics that describe the difficulty of a level.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LevelStats {
+    /// Number of cells inside the walls of the level.
+    pub floor_area: usize,
+    pub box_count: usize,
+    /// Number of cells from which a box can never be pushed to a goal.
+    pub dead_squares: usize,
+    /// Number of cells that have walls on two opposite sides.
+    pub corridor_cells: usize,
+    /// Length of the push optimal solution, if found within the limits.
+    pub push_optimal: Option<SolutionLength>,
+    /// Length of the move optimal solution, if found within the limits.
+    pub move_optimal: Option<SolutionLength>,
+    /// Number of box lines of the push optimal solution.
+    pub box_lines: Option<usize>,
+    /// Number of box changes of the push optimal solution.
+    pub box_changes: Option<usize>,
+    /// Number of states the solver explored for both solutions.
+    pub states_explored: usize,
+}
+
+impl LevelStats {
+    /// A score for the difficulty of the level, higher is harder.
+    /// The score is the sum of the pushes, box lines and twice the box changes
+    /// of the push optimal solution, plus the binary logarithm of the explored states.
+    /// Returns `None` if the level was not solved within the limits.
+    pub fn difficulty(&self) -> Option<f64> {
+        let pushes = self.push_optimal?.pushes as f64;
+        let box_lines = self.box_lines? as f64;
+        let box_changes = self.box_changes? as f64;
+
+        Some(pushes + box_lines + 2.0 * box_changes + (self.states_explored as f64 + 1.0).log2())
+    }
+}
+
+/// Number of moves and pushes of a solution.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SolutionLength {
+    pub moves: usize,
+    pub pushes: usize,
+}
+
+impl From<&Solution> for SolutionLength {
+    fn from(solution: &Solution) -> Self {
+        Self {
+            moves: solution.move_count(),
+            pushes: solution.push_count(),
+        }
+    }
+}
+
+/// Analyze the level with the default solver limits.
+pub fn analyze(level: &Level) -> LevelStats {
+    analyze_with_limits(level, &SolverLimits::default())
+}
+
+/// Analyze the level. The solutions are only calculated
+/// as long as the solver stays within the given limits.
+pub fn analyze_with_limits(level: &Level, limits: &SolverLimits) -> LevelStats {
+    let push_optimal = solve(level, Metric::Pushes, limits);
+    let move_optimal = solve(level, Metric::Moves, limits);
+
+    LevelStats {
+        floor_area: level.iter().filter(|cell| cell.is_inside()).count(),
+        box_count: level.iter().filter(|cell| cell.is_box()).count(),
+        dead_squares: dead_squares(level).iter().filter(|&&dead| dead).count(),
+        corridor_cells: count_corridor_cells(level),
+        push_optimal: push_optimal.as_ref().ok().map(SolutionLength::from),
+        move_optimal: move_optimal.as_ref().ok().map(SolutionLength::from),
+        box_lines: push_optimal.as_ref().ok().map(Solution::box_lines),
+        box_changes: push_optimal.as_ref().ok().map(Solution::box_changes),
+        states_explored: states_explored(&push_optimal) + states_explored(&move_optimal),
+    }
+}
+
+fn states_explored(result: &Result<Solution, SolveError>) -> usize {
+    match result {
+        Ok(solution) => solution.states_explored,
+        Err(error) => error.states_explored(),
+    }
+}
+
+/// Count the cells inside the level that have walls
+/// to the left and right or to the top and bottom.
+fn count_corridor_cells(level: &Level) -> usize {
+    let (height, width) = level.dim();
+    let is_wall = |y: usize, x: usize| !level[[y, x]].is_inside();
+
+    level
+        .indexed_iter()
+        .filter(|(_, cell)| cell.is_inside())
+        .filter(|((y, x), _)| {
+            let (y, x) = (*y, *x);
+            let horizontal = (x == 0 || is_wall(y, x - 1)) && (x == width - 1 || is_wall(y, x + 1));
+            let vertical = (y == 0 || is_wall(y - 1, x)) && (y == height - 1 || is_wall(y + 1, x));
+            horizontal || vertical
+        })
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level::parse_level;
+
+    #[test]
+    fn analyze_corridor() {
+        let stats = analyze(&parse_level("#######\n#@ $ .#\n#######").unwrap());
+
+        assert_eq!(stats.floor_area, 5);
+        assert_eq!(stats.box_count, 1);
+        assert_eq!(stats.dead_squares, 1);
+        assert_eq!(stats.corridor_cells, 5);
+        assert_eq!(stats.push_optimal, Some(SolutionLength { moves: 3, pushes: 2 }));
+        assert_eq!(stats.move_optimal, Some(SolutionLength { moves: 3, pushes: 2 }));
+        assert_eq!(stats.box_lines, Some(1));
+        assert_eq!(stats.box_changes, Some(0));
+        // 2 pushes and 1 box line.
+        assert_eq!(stats.difficulty(), Some(3.0 + (stats.states_explored as f64 + 1.0).log2()));
+    }
+
+    #[test]
+    fn analyze_room_with_two_boxes() {
+        let stats = analyze(&parse_level("######\n#@   #\n# $$ #\n# .. #\n######").unwrap());
+
+        assert_eq!(stats.corridor_cells, 0);
+        assert_eq!(stats.push_optimal.map(|length| length.pushes), Some(2));
+        assert_eq!(stats.move_optimal, Some(SolutionLength { moves: 5, pushes: 2 }));
+        assert_eq!(stats.box_changes, Some(1));
+        // 2 pushes, 2 box lines and 1 box change.
+        assert_eq!(stats.difficulty(), Some(6.0 + (stats.states_explored as f64 + 1.0).log2()));
+    }
+
+    #[test]
+    fn analyze_unsolvable_level() {
+        let stats = analyze(&parse_level("#####\n#$  #\n# @.#\n#####").unwrap());
+
+        assert_eq!(stats.dead_squares, 4);
+        assert_eq!(stats.push_optimal, None);
+        assert_eq!(stats.move_optimal, None);
+        assert_eq!(stats.difficulty(), None);
+    }
+}
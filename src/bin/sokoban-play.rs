@@ -0,0 +1,221 @@
+//! Play generated or loaded sokoban levels in the terminal.
+//!
+//! ```text
+//! sokoban-play [--width 3] [--height 3] [--boxes 2] [--pack levels.sok] [--theme ascii|unicode|emoji|ansi]
+//! ```
+//!
+//! Move with the arrow keys or WASD, undo with `u` or backspace, redo with `y`,
+//! restart with `r`, get a hint with `h`, go to the next level with `n` and quit
+//! with `q` or escape.
+
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::process;
+use std::time::Duration;
+
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{execute, queue};
+use sokoban_level_generator::{
+    hint, parse_pack, render_terminal, try_generate_level, Direction, Game, GenerationConfig, Level,
+    SolverLimits, TerminalOptions, Theme,
+};
+
+/// Source of the levels, either a pack file or the generator.
+enum Levels {
+    Pack { levels: Vec<Level>, next: usize },
+    Generated(GenerationConfig),
+}
+
+impl Levels {
+    fn next(&mut self) -> Result<Level, String> {
+        match self {
+            Self::Pack { levels, next } => {
+                let level = levels.get(*next).cloned().ok_or("there are no more levels in the pack")?;
+                *next += 1;
+                Ok(level)
+            }
+            Self::Generated(config) => try_generate_level(config).map_err(|error| error.to_string()),
+        }
+    }
+}
+
+/// Restores the terminal when the game ends, even on errors.
+struct RawTerminal;
+
+impl RawTerminal {
+    fn enter() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        execute!(io::stdout(), EnterAlternateScreen, Hide)?;
+        Ok(Self)
+    }
+}
+
+impl Drop for RawTerminal {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), Show, LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+fn main() {
+    let (mut levels, theme) = parse_arguments().unwrap_or_else(|error| {
+        eprintln!("{}", error);
+        eprintln!(
+            "usage: sokoban-play [--width N] [--height N] [--boxes N] [--pack FILE] [--theme ascii|unicode|emoji|ansi]"
+        );
+        process::exit(2);
+    });
+
+    if let Err(error) = play(&mut levels, theme) {
+        eprintln!("{}", error);
+        process::exit(1);
+    }
+}
+
+fn parse_arguments() -> Result<(Levels, Theme), String> {
+    let mut config = GenerationConfig::new(3, 3, 2);
+    config.max_attempts = Some(10_000);
+    let mut pack = None;
+    let mut theme = Theme::Unicode;
+
+    let mut arguments = env::args().skip(1);
+    while let Some(argument) = arguments.next() {
+        let value = arguments.next().ok_or(format!("missing value for {}", argument))?;
+        let number = || value.parse::<u8>().map_err(|_| format!("invalid number '{}'", value));
+
+        match argument.as_str() {
+            "--width" => config.width = number()?,
+            "--height" => config.height = number()?,
+            "--boxes" => config.boxes = number()?,
+            "--pack" => pack = Some(value),
+            "--theme" => {
+                theme = match value.as_str() {
+                    "ascii" => Theme::Ascii,
+                    "unicode" => Theme::Unicode,
+                    "emoji" => Theme::Emoji,
+                    "ansi" => Theme::Ansi,
+                    _ => return Err(format!("unknown theme '{}'", value)),
+                }
+            }
+            _ => return Err(format!("unknown argument '{}'", argument)),
+        }
+    }
+
+    let levels = match pack {
+        Some(path) => {
+            let text = fs::read_to_string(&path).map_err(|error| format!("cannot read {}: {}", path, error))?;
+            let entries = parse_pack(&text).map_err(|error| format!("cannot read {}: {}", path, error))?;
+            let levels = entries.into_iter().map(|entry| entry.level).collect::<Vec<_>>();
+            if levels.is_empty() {
+                return Err(format!("{} does not contain any levels", path));
+            }
+            Levels::Pack { levels, next: 0 }
+        }
+        None => Levels::Generated(config),
+    };
+
+    Ok((levels, theme))
+}
+
+fn play(levels: &mut Levels, theme: Theme) -> Result<(), String> {
+    let mut game = Game::new(levels.next()?).map_err(|error| error.to_string())?;
+    let mut number = 1;
+    let mut message = String::new();
+    let options = TerminalOptions { theme, overlay: None };
+    let limits = SolverLimits {
+        max_duration: Some(Duration::from_secs(2)),
+        ..Default::default()
+    };
+
+    let _terminal = RawTerminal::enter().map_err(|error| error.to_string())?;
+
+    loop {
+        if game.is_solved() {
+            message = "Solved! Press n for the next level.".to_string();
+        }
+        draw(&game, number, &options, &message).map_err(|error| error.to_string())?;
+
+        let Event::Key(key) = event::read().map_err(|error| error.to_string())? else {
+            continue;
+        };
+        if key.kind == KeyEventKind::Release {
+            continue;
+        }
+
+        message.clear();
+        let direction = match key.code {
+            KeyCode::Up | KeyCode::Char('w') => Some(Direction::Up),
+            KeyCode::Down | KeyCode::Char('s') => Some(Direction::Down),
+            KeyCode::Left | KeyCode::Char('a') => Some(Direction::Left),
+            KeyCode::Right | KeyCode::Char('d') => Some(Direction::Right),
+            KeyCode::Char('u') | KeyCode::Backspace => {
+                game.undo();
+                None
+            }
+            KeyCode::Char('y') => {
+                game.redo();
+                None
+            }
+            KeyCode::Char('r') => {
+                game.restart();
+                None
+            }
+            KeyCode::Char('h') => {
+                message = match hint(game.level(), &limits) {
+                    Ok(Some(hint)) => format!(
+                        "Hint: {} ({} pushes remaining)",
+                        hint.moves, hint.remaining_pushes
+                    ),
+                    Ok(None) => "The level is solved.".to_string(),
+                    Err(error) => format!("No hint: {}. Try to undo.", error),
+                };
+                None
+            }
+            KeyCode::Char('n') => {
+                match levels.next().and_then(|level| Game::new(level).map_err(|error| error.to_string())) {
+                    Ok(next) => {
+                        game = next;
+                        number += 1;
+                    }
+                    Err(error) => message = error,
+                }
+                None
+            }
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+            _ => None,
+        };
+
+        if let Some(direction) = direction {
+            if !game.is_solved() {
+                game.make_move(direction);
+            }
+        }
+    }
+}
+
+fn draw(game: &Game, number: usize, options: &TerminalOptions, message: &str) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    queue!(stdout, MoveTo(0, 0), Clear(ClearType::All))?;
+
+    // the terminal is in raw mode, lines need a carriage return.
+    write!(stdout, "Level {}\r\n\r\n", number)?;
+    for line in render_terminal(game.level(), options).lines() {
+        write!(stdout, "{}\r\n", line)?;
+    }
+    write!(
+        stdout,
+        "\r\nMoves: {}  Pushes: {}\r\n{}\r\n\r\n",
+        game.move_count(),
+        game.push_count(),
+        message
+    )?;
+    write!(
+        stdout,
+        "arrows/wasd: move  u: undo  y: redo  r: restart  h: hint  n: next level  q: quit\r\n"
+    )?;
+
+    stdout.flush()
+}
@@ -0,0 +1,410 @@
+//! A local HTTP service that generates, verifies and solves sokoban levels.
+//!
+//! ```text
+//! sokoban-server [--bind 127.0.0.1:8080] [--budget-ms 5000]
+//! ```
+//!
+//! - `GET /level?w=3&h=3&boxes=2&seed=42` generates a level (all parameters are optional).
+//! - `POST /verify` with `{"level": "...", "moves": "..."}` replays the moves.
+//! - `POST /solve` with `{"level": "...", "metric": "pushes"}` solves the level.
+//!
+//! Levels are sent as the string of `encode_level` or in the pretty form
+//! (rows separated by newlines). Generating and solving stop when the budget
+//! is used up, so a single slow request cannot tie up the service. Connections
+//! above the limit are rejected with 503, headers above 8 KiB with 431.
+
+use std::env;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde_json::{json, Value};
+use sokoban_level_generator::{
+    decode_level, encode_level, parse_level, pretty_print_level, solve, try_generate_level, Game,
+    GenerationConfig, GenerationError, Level, Metric, SolveError, SolverLimits,
+};
+
+/// Maximum size of a request body.
+const MAX_BODY: usize = 64 * 1024;
+
+/// Maximum size of the request line and the headers together.
+const MAX_HEADERS: u64 = 8 * 1024;
+
+/// Maximum number of rooms in each direction and boxes of a generated level.
+const MAX_ROOMS: u8 = 8;
+const MAX_BOXES: u8 = 8;
+
+/// Maximum number of connections that are handled at the same time.
+/// Further connections are answered with 503 right away.
+const MAX_CONNECTIONS: usize = 16;
+
+struct Request {
+    method: String,
+    path: String,
+    query: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+struct Response {
+    status: u16,
+    body: Value,
+}
+
+impl Response {
+    fn ok(body: Value) -> Self {
+        Self { status: 200, body }
+    }
+
+    fn error(status: u16, message: impl ToString) -> Self {
+        Self {
+            status,
+            body: json!({ "error": message.to_string() }),
+        }
+    }
+}
+
+fn main() {
+    let mut bind = "127.0.0.1:8080".to_string();
+    let mut budget = Duration::from_secs(5);
+
+    let mut arguments = env::args().skip(1);
+    while let Some(argument) = arguments.next() {
+        match (argument.as_str(), arguments.next()) {
+            ("--bind", Some(value)) => bind = value,
+            ("--budget-ms", Some(value)) => match value.parse() {
+                Ok(millis) => budget = Duration::from_millis(millis),
+                Err(_) => exit_with_usage(&format!("invalid budget '{}'", value)),
+            },
+            _ => exit_with_usage(&format!("unknown argument '{}'", argument)),
+        }
+    }
+
+    let listener = TcpListener::bind(&bind).unwrap_or_else(|error| {
+        eprintln!("cannot bind to {}: {}", bind, error);
+        process::exit(1);
+    });
+    eprintln!("listening on http://{}", bind);
+
+    serve(listener, budget, MAX_CONNECTIONS);
+}
+
+fn exit_with_usage(error: &str) -> ! {
+    eprintln!("{}", error);
+    eprintln!("usage: sokoban-server [--bind ADDRESS] [--budget-ms MILLISECONDS]");
+    process::exit(2);
+}
+
+/// Handle every connection on its own thread, up to `max_connections` at the same time.
+fn serve(listener: TcpListener, budget: Duration, max_connections: usize) {
+    let active = Arc::new(AtomicUsize::new(0));
+
+    for stream in listener.incoming().flatten() {
+        if active.fetch_add(1, Ordering::SeqCst) >= max_connections {
+            active.fetch_sub(1, Ordering::SeqCst);
+            let _ = write_response(&stream, &Response::error(503, "too many requests, try again later"));
+            continue;
+        }
+
+        let active = Arc::clone(&active);
+        thread::spawn(move || {
+            let _ = stream.set_read_timeout(Some(Duration::from_secs(5)));
+            let response = match read_request(&stream) {
+                Ok(request) => handle(&request, budget),
+                Err(response) => response,
+            };
+            let _ = write_response(&stream, &response);
+            active.fetch_sub(1, Ordering::SeqCst);
+        });
+    }
+}
+
+fn read_request(stream: &TcpStream) -> Result<Request, Response> {
+    let bad_request = |message: &str| Response::error(400, message);
+    let too_large = || Response::error(431, "the headers are too large");
+    let mut reader = BufReader::new(stream).take(MAX_HEADERS);
+
+    let mut line = String::new();
+    reader.read_line(&mut line).map_err(|_| bad_request("cannot read the request"))?;
+    if !line.ends_with('\n') && reader.limit() == 0 {
+        return Err(too_large());
+    }
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return Err(bad_request("invalid request line"));
+    };
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+
+    let mut content_length = 0;
+    loop {
+        let mut header = String::new();
+        reader.read_line(&mut header).map_err(|_| bad_request("cannot read the headers"))?;
+        if !header.ends_with('\n') && reader.limit() == 0 {
+            return Err(too_large());
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().map_err(|_| bad_request("invalid content length"))?;
+            }
+        }
+    }
+
+    if content_length > MAX_BODY {
+        return Err(Response::error(413, "the body is too large"));
+    }
+    let mut body = vec![0; content_length];
+    reader.into_inner().read_exact(&mut body).map_err(|_| bad_request("cannot read the body"))?;
+
+    Ok(Request {
+        method: method.to_string(),
+        path: path.to_string(),
+        query: query
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect(),
+        body,
+    })
+}
+
+fn write_response(mut stream: &TcpStream, response: &Response) -> std::io::Result<()> {
+    let body = response.body.to_string();
+    let reason = match response.status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        413 => "Payload Too Large",
+        422 => "Unprocessable Entity",
+        431 => "Request Header Fields Too Large",
+        503 => "Service Unavailable",
+        _ => "Unknown",
+    };
+
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        response.status,
+        reason,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+fn handle(request: &Request, budget: Duration) -> Response {
+    match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/level") => generate(&request.query, budget),
+        ("POST", "/verify") => verify(&request.body),
+        ("POST", "/solve") => solve_level(&request.body, &solver_limits(budget)),
+        _ => Response::error(404, "unknown endpoint"),
+    }
+}
+
+/// Limits of the solver that stop it when the budget is used up.
+fn solver_limits(budget: Duration) -> SolverLimits {
+    SolverLimits {
+        max_duration: Some(budget),
+        ..Default::default()
+    }
+}
+
+/// `GET /level`: generate a level and solve it (push optimal). Both share the budget,
+/// the solver only gets the time that is left after the generation.
+fn generate(query: &[(String, String)], budget: Duration) -> Response {
+    let parameter = |name: &str| query.iter().find(|(key, _)| key == name).map(|(_, value)| value.as_str());
+    let number = |name: &str, default: u8, max: u8| match parameter(name) {
+        None => Ok(default),
+        Some(value) => value
+            .parse::<u8>()
+            .ok()
+            .filter(|number| (1..=max).contains(number))
+            .ok_or_else(|| Response::error(400, format!("'{}' must be a number from 1 to {}", name, max))),
+    };
+
+    let dimensions = (
+        number("w", 3, MAX_ROOMS),
+        number("h", 3, MAX_ROOMS),
+        number("boxes", 2, MAX_BOXES),
+    );
+    let (width, height, boxes) = match dimensions {
+        (Ok(width), Ok(height), Ok(boxes)) => (width, height, boxes),
+        (Err(response), _, _) | (_, Err(response), _) | (_, _, Err(response)) => return response,
+    };
+    let seed = match parameter("seed").map(str::parse::<u64>) {
+        None => rand::random(),
+        Some(Ok(seed)) => seed,
+        Some(Err(_)) => return Response::error(400, "'seed' must be a number"),
+    };
+
+    let start = Instant::now();
+    let mut config = GenerationConfig::new(height, width, boxes);
+    config.seed = Some(seed);
+    config.max_duration = Some(budget);
+
+    let level = match try_generate_level(&config) {
+        Ok(level) => level,
+        Err(error @ GenerationError::TimeLimitReached { .. }) => return Response::error(503, error),
+        Err(error) => return Response::error(422, error),
+    };
+    let limits = solver_limits(budget.saturating_sub(start.elapsed()));
+    let solution = solve(&level, Metric::Pushes, &limits).ok();
+
+    Response::ok(json!({
+        "encoded": encode_level(&level),
+        "pretty": pretty_print_level(&level),
+        "width": level.ncols(),
+        "height": level.nrows(),
+        "boxes": boxes,
+        "seed": seed,
+        "solution": solution.as_ref().map(|solution| &solution.moves),
+        "moves": solution.as_ref().map(|solution| solution.move_count()),
+        "pushes": solution.as_ref().map(|solution| solution.push_count()),
+    }))
+}
+
+/// `POST /verify`: replay the moves and check if they solve the level.
+fn verify(body: &[u8]) -> Response {
+    let (level, body) = match read_level(body) {
+        Ok(result) => result,
+        Err(response) => return response,
+    };
+    let Some(moves) = body.get("moves").and_then(Value::as_str) else {
+        return Response::error(400, "'moves' is missing");
+    };
+
+    let mut game = match Game::new(level) {
+        Ok(game) => game,
+        Err(error) => return Response::error(422, error),
+    };
+
+    match game.apply_moves(moves) {
+        Ok(()) => Response::ok(json!({
+            "valid": true,
+            "solved": game.is_solved(),
+            "moves": game.move_count(),
+            "pushes": game.push_count(),
+        })),
+        Err(error) => Response::ok(json!({
+            "valid": false,
+            "solved": false,
+            "error": error.to_string(),
+        })),
+    }
+}
+
+/// `POST /solve`: solve the level with the given metric (pushes by default).
+fn solve_level(body: &[u8], limits: &SolverLimits) -> Response {
+    let (level, body) = match read_level(body) {
+        Ok(result) => result,
+        Err(response) => return response,
+    };
+    let metric = match body.get("metric").and_then(Value::as_str) {
+        None | Some("pushes") => Metric::Pushes,
+        Some("moves") => Metric::Moves,
+        Some(metric) => return Response::error(400, format!("unknown metric '{}'", metric)),
+    };
+
+    match solve(&level, metric, limits) {
+        Ok(solution) => Response::ok(json!({
+            "solution": solution.moves,
+            "moves": solution.move_count(),
+            "pushes": solution.push_count(),
+            "states_explored": solution.states_explored,
+        })),
+        Err(error @ SolveError::LimitReached { .. }) => Response::error(503, error),
+        Err(error) => Response::error(422, error),
+    }
+}
+
+/// Read the JSON body and the level of the `level` field.
+fn read_level(body: &[u8]) -> Result<(Level, Value), Response> {
+    let body: Value = serde_json::from_slice(body).map_err(|error| Response::error(400, error))?;
+    let text = body
+        .get("level")
+        .and_then(Value::as_str)
+        .ok_or_else(|| Response::error(400, "'level' is missing"))?;
+
+    let level = if text.contains('\n') {
+        parse_level(text)
+    } else {
+        decode_level(text)
+    };
+
+    level.map(|level| (level, body)).map_err(|error| Response::error(400, error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(method: &str, path: &str, body: &str) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        thread::spawn(move || serve(listener, Duration::from_secs(5), MAX_CONNECTIONS));
+
+        let mut stream = TcpStream::connect(address).unwrap();
+        write!(stream, "{} {} HTTP/1.1\r\nContent-Length: {}\r\n\r\n{}", method, path, body.len(), body).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    #[test]
+    fn generate_seeded_levels() {
+        let first = request("GET", "/level?w=2&h=2&boxes=1&seed=7", "");
+        let second = request("GET", "/level?w=2&h=2&boxes=1&seed=7", "");
+
+        assert!(first.starts_with("HTTP/1.1 200 OK"));
+        assert!(first.contains("\"seed\":7"));
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn verify_and_solve_levels() {
+        let verified = request("POST", "/verify", r#"{"level": "7#|1#1@1-1$1-1.1#|7#", "moves": "rRR"}"#);
+        let solved = request("POST", "/solve", "{\"level\": \"#######\\n#@ $ .#\\n#######\", \"metric\": \"moves\"}");
+
+        assert!(verified.ends_with(r#"{"moves":3,"pushes":2,"solved":true,"valid":true}"#));
+        assert!(solved.contains(r#""solution":"rRR""#));
+        assert!(request("GET", "/unknown", "").starts_with("HTTP/1.1 404"));
+    }
+
+    #[test]
+    fn reject_too_large_headers() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        thread::spawn(move || serve(listener, Duration::from_secs(5), MAX_CONNECTIONS));
+
+        // the last header is cut off at the limit.
+        let head = "GET /level HTTP/1.1\r\nX-Padding: ";
+        let mut stream = TcpStream::connect(address).unwrap();
+        write!(stream, "{}{}", head, "a".repeat(MAX_HEADERS as usize - head.len())).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+
+        assert!(response.starts_with("HTTP/1.1 431 Request Header Fields Too Large"));
+    }
+
+    #[test]
+    fn reject_connections_above_the_limit() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        thread::spawn(move || serve(listener, Duration::from_secs(5), 1));
+
+        // the first connection keeps its slot while it has not sent a request.
+        let idle = TcpStream::connect(address).unwrap();
+        let mut stream = TcpStream::connect(address).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+
+        assert!(response.starts_with("HTTP/1.1 503 Service Unavailable"));
+        drop(idle);
+    }
+}
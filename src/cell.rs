@@ -1,3 +1,4 @@
+/// A single field of a sokoban level.
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Cell {
     Empty,
@@ -12,7 +13,10 @@ pub enum Cell {
 }
 
 impl Cell {
-    pub(crate) fn to_char(&self) -> char {
+    /// Returns the character of the cell in the
+    /// [JSoko Level Format](https://www.sokoban-online.de/sokoban/levell-format/).
+    /// All kinds of floors (and empty cells) are printed as a space.
+    pub fn to_char(self) -> char {
         match self {
             Self::Wall => '#',
             Self::Box => '$',
@@ -24,31 +28,150 @@ impl Cell {
         }
     }
 
-    pub(crate) fn to_encoding_char(&self) -> char {
+    /// Parses a character of the
+    /// [JSoko Level Format](https://www.sokoban-online.de/sokoban/levell-format/).
+    /// Spaces, dashes and underscores are parsed as floor, since the format
+    /// does not distinguish floors from empty cells outside the level.
+    pub fn from_char(char: char) -> Option<Self> {
+        match char {
+            '#' => Some(Self::Wall),
+            '$' => Some(Self::Box),
+            '*' => Some(Self::BoxOnGoal),
+            '.' => Some(Self::Goal),
+            '@' => Some(Self::Player),
+            '+' => Some(Self::PlayerOnGoal),
+            ' ' | '-' | '_' => Some(Self::Floor),
+            _ => None,
+        }
+    }
+
+    /// Returns the character of the cell for serde. Unlike [`to_char`](Self::to_char),
+    /// empty cells (`~`) and special floors (`%`) have their own characters. `%` is
+    /// not a character of the level format, where `_` is a floor.
+    #[cfg(feature = "serde")]
+    pub(crate) fn to_serde_char(self) -> char {
         match self {
-            Self::Empty | Self::Floor | Self::SpecialFloor => '-',
+            Self::Empty => '~',
+            Self::SpecialFloor => '%',
             _ => self.to_char(),
         }
     }
 
-    pub(crate) fn is_floor(&self) -> bool {
-        match self {
-            Self::Floor | Self::SpecialFloor => true,
-            _ => false,
+    /// Parses a character of [`to_serde_char`](Self::to_serde_char). Other floor
+    /// characters of the level format (including `_`) are parsed as floor.
+    #[cfg(feature = "serde")]
+    pub(crate) fn from_serde_char(char: char) -> Option<Self> {
+        match char {
+            '~' => Some(Self::Empty),
+            '%' => Some(Self::SpecialFloor),
+            _ => Self::from_char(char),
         }
     }
 
-    pub(crate) fn is_box(&self) -> bool {
+    pub(crate) fn to_encoding_char(self) -> char {
         match self {
-            Self::Box | Self::BoxOnGoal => true,
-            _ => false,
+            Self::Empty | Self::Floor | Self::SpecialFloor => '-',
+            _ => self.to_char(),
         }
     }
 
+    pub(crate) fn is_floor(&self) -> bool {
+        matches!(self, Self::Floor | Self::SpecialFloor)
+    }
+
+    pub(crate) fn is_box(&self) -> bool {
+        matches!(self, Self::Box | Self::BoxOnGoal)
+    }
+
     pub(crate) fn is_walkable(&self) -> bool {
+        matches!(self, Self::Floor | Self::SpecialFloor | Self::Goal)
+    }
+
+    pub(crate) fn is_goal(&self) -> bool {
+        matches!(self, Self::Goal | Self::BoxOnGoal | Self::PlayerOnGoal)
+    }
+
+    pub(crate) fn is_player(&self) -> bool {
+        matches!(self, Self::Player | Self::PlayerOnGoal)
+    }
+
+    /// Returns the cell that remains when the player or a box leaves the cell.
+    pub(crate) fn without_entity(self) -> Self {
         match self {
-            Self::Floor | Self::SpecialFloor | Self::Goal => true,
-            _ => false,
+            Self::Player | Self::Box => Self::Floor,
+            Self::PlayerOnGoal | Self::BoxOnGoal => Self::Goal,
+            cell => cell,
         }
     }
+
+    /// Returns the cell with the player on it.
+    pub(crate) fn with_player(self) -> Self {
+        if self.is_goal() {
+            Self::PlayerOnGoal
+        } else {
+            Self::Player
+        }
+    }
+
+    /// Returns the cell with a box on it.
+    pub(crate) fn with_box(self) -> Self {
+        if self.is_goal() {
+            Self::BoxOnGoal
+        } else {
+            Self::Box
+        }
+    }
+
+    /// Returns true if the cell is part of the playable area of the level.
+    pub(crate) fn is_inside(&self) -> bool {
+        !matches!(self, Self::Wall | Self::Empty)
+    }
+}
+
+/// Cells are serialized as their character in the
+/// [JSoko Level Format](https://www.sokoban-online.de/sokoban/levell-format/).
+/// The format has a single character for all kinds of floors, so empty cells
+/// are serialized as `~` and special floors as `_` to keep them apart.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Cell {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_char(self.to_serde_char())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Cell {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let char = char::deserialize(deserializer)?;
+        Cell::from_serde_char(char).ok_or_else(|| {
+            serde::de::Error::invalid_value(serde::de::Unexpected::Char(char), &"a sokoban cell")
+        })
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serialize_every_cell() {
+        let cells = [
+            Cell::Empty,
+            Cell::Wall,
+            Cell::Player,
+            Cell::PlayerOnGoal,
+            Cell::Box,
+            Cell::BoxOnGoal,
+            Cell::Goal,
+            Cell::Floor,
+            Cell::SpecialFloor,
+        ];
+
+        let json = serde_json::to_string(&cells).unwrap();
+
+        assert_eq!(json, r##"["~","#","@","+","$","*","."," ","%"]"##);
+        assert_eq!(serde_json::from_str::<Vec<Cell>>(&json).unwrap(), cells);
+        assert_eq!(serde_json::from_str::<Cell>(r#""-""#).unwrap(), Cell::Floor);
+        assert_eq!(serde_json::from_str::<Cell>(r#""_""#).unwrap(), Cell::Floor);
+    }
 }
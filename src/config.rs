@@ -0,0 +1,112 @@
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::constraints::ConstraintMask;
+use crate::scoring::{PushScorer, StateScorer};
+use crate::solver::SolverLimits;
+
+/// Configuration for the generation of a level.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GenerationConfig {
+    /// Number of rooms (3x3 cells) in the vertical direction.
+    pub height: u8,
+    /// Number of rooms (3x3 cells) in the horizontal direction.
+    pub width: u8,
+    /// Number of boxes (and goals) of the level.
+    pub boxes: u8,
+    /// Maximum number of attempts before the generation fails.
+    /// Every generated layout and every placement of the entities counts
+    /// as an attempt, also layouts that are rejected by the requirements.
+    /// Most layouts are rejected, so even 3x3 rooms with 2 boxes often need
+    /// several hundred attempts. There is no limit if `None`.
+    pub max_attempts: Option<u32>,
+    /// Maximum duration of the generation. The time is checked between
+    /// the attempts and during the reverse search. There is no limit if `None`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub max_duration: Option<Duration>,
+    /// Seed of the random number generator. The same seed and configuration
+    /// generate the same level. A random seed is used if `None`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub seed: Option<u64>,
+    /// Required number of pushes of the push optimal solution.
+    pub pushes: Option<RangeInclusive<usize>>,
+    /// Required difficulty score (see [`LevelStats::difficulty`](crate::LevelStats::difficulty)).
+    pub difficulty: Option<RangeInclusive<f64>>,
+    /// Minimum number of pushes of every box from its start to the nearest goal,
+    /// in any solution.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub min_pushes_per_box: u32,
+    /// Reject start states where a box already stands on a goal.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub forbid_boxes_on_goals: bool,
+    /// Chooses the start state among all states of the reverse search.
+    /// Scorers cannot be serialized, deserialized configurations use the [`PushScorer`].
+    #[cfg_attr(feature = "serde", serde(skip, default = "default_scorer"))]
+    pub scorer: Arc<dyn StateScorer>,
+    /// Limits of the solver that checks the pushes and difficulty.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub solver_limits: SolverLimits,
+    /// Fixed cells of the layout that the generated levels keep. The mask must
+    /// have the size of the rooms, see [`ConstraintMask::new`].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub constraints: Option<ConstraintMask>,
+}
+
+impl GenerationConfig {
+    /// Create a configuration without constraints and attempt limit.
+    pub fn new(height: u8, width: u8, boxes: u8) -> Self {
+        Self {
+            height,
+            width,
+            boxes,
+            max_attempts: None,
+            max_duration: None,
+            seed: None,
+            pushes: None,
+            difficulty: None,
+            min_pushes_per_box: 0,
+            forbid_boxes_on_goals: false,
+            scorer: default_scorer(),
+            solver_limits: SolverLimits::default(),
+            constraints: None,
+        }
+    }
+}
+
+fn default_scorer() -> Arc<dyn StateScorer> {
+    Arc::new(PushScorer)
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use super::*;
+    use crate::constraints::CellConstraint;
+    use crate::scoring::MoveScorer;
+
+    #[test]
+    fn round_trip_config() {
+        let mut config = GenerationConfig::new(3, 2, 2);
+        config.max_attempts = Some(100);
+        config.max_duration = Some(Duration::from_millis(1500));
+        config.seed = Some(42);
+        config.pushes = Some(4..=20);
+        config.difficulty = Some(1.5..=8.0);
+        config.min_pushes_per_box = 2;
+        config.forbid_boxes_on_goals = true;
+        config.scorer = Arc::new(MoveScorer);
+        config.solver_limits.max_states = 1000;
+        let mut constraints = ConstraintMask::new(3, 2);
+        constraints.set(1, 1, CellConstraint::Goal).unwrap();
+        config.constraints = Some(constraints);
+
+        let json = serde_json::to_string(&config).unwrap();
+        let deserialized: GenerationConfig = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(serde_json::to_string(&deserialized).unwrap(), json);
+        assert_eq!(deserialized.constraints, config.constraints);
+        // scorers are not serialized.
+        assert_eq!(format!("{:?}", deserialized.scorer), "PushScorer");
+    }
+}
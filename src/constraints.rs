@@ -0,0 +1,403 @@
+use std::error::Error;
+use std::fmt;
+
+use ndarray::{s, Array2};
+
+use crate::cell::Cell;
+use crate::level::{failed_requirement, Level, Requirement};
+use crate::room::{all_rooms, Room};
+
+/// Constraint of a single cell of a layout, see [`ConstraintMask`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CellConstraint {
+    /// The generator chooses the cell.
+    #[default]
+    Free,
+    Wall,
+    Floor,
+    /// A floor with a goal.
+    Goal,
+    /// A floor where the player may start. If the mask has such cells,
+    /// the player starts on one of them.
+    PlayerArea,
+}
+
+/// Error that occurs when constraints are invalid or contradictory.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConstraintError {
+    /// The cell is outside of the mask.
+    OutOfBounds { row: usize, column: usize },
+    /// A preset room is not 3x3 cells of walls and floors.
+    InvalidRoom,
+    /// The size of the mask does not match the size of the level (rows, columns).
+    SizeMismatch { mask: (usize, usize), level: (usize, usize) },
+    /// The mask has more goals than the configuration has boxes.
+    TooManyGoals { goals: usize, boxes: u8 },
+    /// No room (template) fits the constraints of the room slot.
+    NoMatchingRoom { room_row: usize, room_column: usize },
+    /// A cell of a given layout contradicts its constraint.
+    LayoutMismatch { row: usize, column: usize },
+    /// A deserialized mask does not have one cell for every row and column.
+    InvalidMask { height: usize, width: usize, cells: usize },
+    /// No layout with the preset cells can meet the requirement.
+    UnmetRequirement(Requirement),
+    /// The given number of layouts in a row failed because of the constraints.
+    Unsatisfiable { layouts: u32 },
+}
+
+impl fmt::Display for ConstraintError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::OutOfBounds { row, column } => {
+                write!(f, "the cell in row {}, column {} is outside of the mask", row, column)
+            }
+            Self::InvalidRoom => write!(f, "a preset room must be 3x3 cells of walls and floors"),
+            Self::SizeMismatch { mask, level } => write!(
+                f,
+                "the mask has {}x{} cells, but the level has {}x{} cells",
+                mask.0, mask.1, level.0, level.1
+            ),
+            Self::TooManyGoals { goals, boxes } => {
+                write!(f, "the mask has {} goals, but there are only {} boxes", goals, boxes)
+            }
+            Self::NoMatchingRoom { room_row, room_column } => write!(
+                f,
+                "no room fits the constraints of the room in row {}, column {}",
+                room_row, room_column
+            ),
+            Self::LayoutMismatch { row, column } => write!(
+                f,
+                "the cell in row {}, column {} contradicts its constraint",
+                row, column
+            ),
+            Self::InvalidMask { height, width, cells } => write!(
+                f,
+                "a mask of {}x{} cells cannot have {} cells",
+                height, width, cells
+            ),
+            Self::UnmetRequirement(requirement) => {
+                write!(f, "no layout with the preset cells meets the requirement {:?}", requirement)
+            }
+            Self::Unsatisfiable { layouts } => write!(
+                f,
+                "{} layouts in a row failed because of the constraints",
+                layouts
+            ),
+        }
+    }
+}
+
+impl Error for ConstraintError {}
+
+/// Pins cells of a layout (the level without the surrounding walls, see
+/// [`frame_level`](crate::frame_level)): fixed walls and floors, fixed goals,
+/// the start area of the player or preset rooms. The generator chooses the
+/// rooms and goals around them.
+///
+/// Positions are (row, column) of the layout, they are shifted by one in
+/// the framed level.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "RawConstraintMask"))]
+pub struct ConstraintMask {
+    height: usize,
+    width: usize,
+    cells: Vec<CellConstraint>,
+}
+
+/// A deserialized mask whose size is not checked yet.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct RawConstraintMask {
+    height: usize,
+    width: usize,
+    cells: Vec<CellConstraint>,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<RawConstraintMask> for ConstraintMask {
+    type Error = ConstraintError;
+
+    fn try_from(mask: RawConstraintMask) -> Result<Self, Self::Error> {
+        let RawConstraintMask { height, width, cells } = mask;
+        if height.checked_mul(width) != Some(cells.len()) {
+            return Err(ConstraintError::InvalidMask {
+                height,
+                width,
+                cells: cells.len(),
+            });
+        }
+
+        Ok(Self { height, width, cells })
+    }
+}
+
+impl ConstraintMask {
+    /// Create a mask without constraints for the given number of rooms
+    /// (see [`GenerationConfig`](crate::GenerationConfig)).
+    pub fn new(height: u8, width: u8) -> Self {
+        Self::with_size(usize::from(height) * 3, usize::from(width) * 3)
+    }
+
+    /// Create a mask without constraints for a layout of the given size in cells,
+    /// e.g. for [`place_entities_on_layout`](crate::place_entities_on_layout).
+    pub fn with_size(height: usize, width: usize) -> Self {
+        Self {
+            height,
+            width,
+            cells: vec![CellConstraint::Free; height * width],
+        }
+    }
+
+    /// The number of rows and columns of the mask.
+    pub fn dim(&self) -> (usize, usize) {
+        (self.height, self.width)
+    }
+
+    /// The constraint of the cell, cells outside of the mask are free.
+    pub fn get(&self, row: usize, column: usize) -> CellConstraint {
+        if row < self.height && column < self.width {
+            self.cells[row * self.width + column]
+        } else {
+            CellConstraint::Free
+        }
+    }
+
+    pub fn set(&mut self, row: usize, column: usize, constraint: CellConstraint) -> Result<(), ConstraintError> {
+        if row >= self.height || column >= self.width {
+            return Err(ConstraintError::OutOfBounds { row, column });
+        }
+
+        self.cells[row * self.width + column] = constraint;
+        Ok(())
+    }
+
+    /// Preset the room (3x3 cells) in the given slot. Walls stay walls, all other
+    /// cells become floors. This includes the empty cells of a room that is read
+    /// with [`parse_level`](crate::parse_level), whose floors at the edge are outside.
+    pub fn set_room(&mut self, room_row: usize, room_column: usize, room: &Level) -> Result<(), ConstraintError> {
+        let valid = room.dim() == (3, 3)
+            && room
+                .iter()
+                .all(|cell| matches!(cell, Cell::Wall | Cell::Floor | Cell::SpecialFloor | Cell::Empty));
+        if !valid {
+            return Err(ConstraintError::InvalidRoom);
+        }
+
+        for ((y, x), cell) in room.indexed_iter() {
+            let constraint = match cell {
+                Cell::Wall => CellConstraint::Wall,
+                _ => CellConstraint::Floor,
+            };
+            self.set(room_row * 3 + y, room_column * 3 + x, constraint)?;
+        }
+
+        Ok(())
+    }
+
+    /// The positions of the fixed goals.
+    pub fn goals(&self) -> Vec<(usize, usize)> {
+        self.positions(CellConstraint::Goal)
+    }
+
+    /// The positions of the start area of the player.
+    pub fn player_area(&self) -> Vec<(usize, usize)> {
+        self.positions(CellConstraint::PlayerArea)
+    }
+
+    fn positions(&self, constraint: CellConstraint) -> Vec<(usize, usize)> {
+        self.cells
+            .iter()
+            .enumerate()
+            .filter(|(_, &cell)| cell == constraint)
+            .map(|(index, _)| (index / self.width, index % self.width))
+            .collect()
+    }
+
+    /// Check if the cell of the layout may be placed at the position.
+    pub(crate) fn allows(&self, row: usize, column: usize, cell: Cell) -> bool {
+        match self.get(row, column) {
+            CellConstraint::Free => true,
+            CellConstraint::Wall => cell == Cell::Wall,
+            CellConstraint::Floor | CellConstraint::Goal | CellConstraint::PlayerArea => cell.is_floor(),
+        }
+    }
+
+    /// Check if the inner 3x3 cells of the room may be placed in the slot.
+    pub(crate) fn allows_room(&self, room_row: usize, room_column: usize, room: &Room) -> bool {
+        room.slice(s![1..=3, 1..=3])
+            .indexed_iter()
+            .all(|((y, x), &cell)| self.allows(room_row * 3 + y, room_column * 3 + x, cell))
+    }
+
+    /// The cells of the slot if all of them are constrained.
+    pub(crate) fn preset_room(&self, room_row: usize, room_column: usize) -> Option<Array2<Cell>> {
+        let mut room = Array2::from_elem((3, 3), Cell::Floor);
+        for ((y, x), cell) in room.indexed_iter_mut() {
+            match self.get(room_row * 3 + y, room_column * 3 + x) {
+                CellConstraint::Free => return None,
+                CellConstraint::Wall => *cell = Cell::Wall,
+                _ => {}
+            }
+        }
+
+        Some(room)
+    }
+
+    /// Check the mask against the size of the level and the box count.
+    pub(crate) fn validate(&self, dim: (usize, usize), boxes: u8) -> Result<(), ConstraintError> {
+        if self.dim() != dim {
+            return Err(ConstraintError::SizeMismatch {
+                mask: self.dim(),
+                level: dim,
+            });
+        }
+
+        let goals = self.goals().len();
+        if goals > boxes.into() {
+            return Err(ConstraintError::TooManyGoals { goals, boxes });
+        }
+
+        Ok(())
+    }
+
+    /// Check that every slot that is not preset can be filled with a room.
+    pub(crate) fn validate_rooms(&self) -> Result<(), ConstraintError> {
+        for room_row in 0..self.height / 3 {
+            for room_column in 0..self.width / 3 {
+                let fits = self.preset_room(room_row, room_column).is_some()
+                    || all_rooms().any(|room| self.allows_room(room_row, room_column, &room));
+                if !fits {
+                    return Err(ConstraintError::NoMatchingRoom { room_row, room_column });
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check the preset cells against the requirements of the layouts: the cells
+    /// that are not walls must have enough space, and a layout whose slots are
+    /// all preset must meet every requirement.
+    pub(crate) fn validate_requirements(&self, boxes: u8) -> Result<(), ConstraintError> {
+        let mut layout = Array2::from_elem((self.height, self.width), Cell::Floor);
+        for ((y, x), cell) in layout.indexed_iter_mut() {
+            if self.get(y, x) == CellConstraint::Wall {
+                *cell = Cell::Wall;
+            }
+        }
+
+        if !Requirement::EnoughSpace.is_met(&layout, boxes) {
+            return Err(ConstraintError::UnmetRequirement(Requirement::EnoughSpace));
+        }
+
+        let preset = self.cells.iter().all(|&cell| cell != CellConstraint::Free);
+        match failed_requirement(&layout, boxes) {
+            Some(requirement) if preset => Err(ConstraintError::UnmetRequirement(requirement)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Check that the cells of the layout meet their constraints.
+    pub(crate) fn validate_layout(&self, layout: &Level) -> Result<(), ConstraintError> {
+        match layout.indexed_iter().find(|((y, x), &cell)| !self.allows(*y, *x, cell)) {
+            Some(((row, column), _)) => Err(ConstraintError::LayoutMismatch { row, column }),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GenerationConfig;
+    use crate::level::{parse_level, try_generate_level, GenerationError};
+
+    #[test]
+    fn generate_with_constraints() {
+        let mut constraints = ConstraintMask::new(2, 2);
+        let room = parse_level("###\n#  \n#  ").unwrap();
+        constraints.set_room(0, 0, &room).unwrap();
+        constraints.set(4, 4, CellConstraint::Goal).unwrap();
+        constraints.set(1, 1, CellConstraint::PlayerArea).unwrap();
+        let mut config = GenerationConfig::new(2, 2, 2);
+        config.seed = Some(1);
+        config.max_attempts = Some(10_000);
+        config.constraints = Some(constraints);
+
+        let level = try_generate_level(&config).unwrap();
+        assert!(level.slice(s![1..4, 1..4]).iter().zip(room.iter()).all(|(cell, room)| {
+            (*cell == Cell::Wall) == (*room == Cell::Wall)
+        }));
+        assert!(level[[5, 5]].is_goal());
+        assert!(level[[2, 2]].is_player());
+    }
+
+    #[test]
+    fn contradictory_constraints() {
+        let mut config = GenerationConfig::new(1, 1, 1);
+        let mut constraints = ConstraintMask::new(1, 1);
+        constraints.set(0, 0, CellConstraint::Goal).unwrap();
+        constraints.set(0, 1, CellConstraint::Goal).unwrap();
+        config.constraints = Some(constraints.clone());
+        assert_eq!(
+            try_generate_level(&config),
+            Err(GenerationError::Constraints(ConstraintError::TooManyGoals { goals: 2, boxes: 1 }))
+        );
+
+        // no template has a floor in the center with walls on all sides but one.
+        constraints = ConstraintMask::new(1, 1);
+        constraints.set_room(0, 0, &parse_level("###\n# #\n###").unwrap()).unwrap();
+        constraints.set(0, 1, CellConstraint::Free).unwrap();
+        config.constraints = Some(constraints);
+        assert_eq!(
+            try_generate_level(&config),
+            Err(GenerationError::Constraints(ConstraintError::NoMatchingRoom { room_row: 0, room_column: 0 }))
+        );
+    }
+
+    #[test]
+    fn unsatisfiable_constraints() {
+        let walls = parse_level("###\n###\n###").unwrap();
+        let mut config = GenerationConfig::new(1, 2, 1);
+        let mut constraints = ConstraintMask::new(1, 2);
+        constraints.set_room(0, 0, &walls).unwrap();
+        constraints.set_room(0, 1, &walls).unwrap();
+        config.constraints = Some(constraints);
+        assert_eq!(
+            try_generate_level(&config),
+            Err(GenerationError::Constraints(ConstraintError::UnmetRequirement(Requirement::EnoughSpace)))
+        );
+
+        // the open room in the middle does not fit next to the walls.
+        constraints = ConstraintMask::new(1, 3);
+        constraints.set_room(0, 0, &walls).unwrap();
+        constraints.set_room(0, 1, &parse_level("##-\n---\n---").unwrap()).unwrap();
+        constraints.set(1, 4, CellConstraint::Free).unwrap();
+        constraints.set_room(0, 2, &walls).unwrap();
+        config = GenerationConfig::new(1, 3, 1);
+        config.seed = Some(1);
+        config.constraints = Some(constraints);
+        assert!(matches!(
+            try_generate_level(&config),
+            Err(GenerationError::Constraints(ConstraintError::Unsatisfiable { .. }))
+        ));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn deserialize_invalid_masks() {
+        let mut mask = ConstraintMask::new(1, 1);
+        mask.set(1, 2, CellConstraint::Goal).unwrap();
+        let json = serde_json::to_string(&mask).unwrap();
+        assert_eq!(serde_json::from_str::<ConstraintMask>(&json).unwrap(), mask);
+
+        let short = r#"{"height": 3, "width": 3, "cells": ["Free"]}"#;
+        let empty = r#"{"height": 3, "width": 0, "cells": ["Goal"]}"#;
+        let overflow = format!(r#"{{"height": {}, "width": 2, "cells": []}}"#, usize::MAX);
+        assert!(serde_json::from_str::<ConstraintMask>(short).is_err());
+        assert!(serde_json::from_str::<ConstraintMask>(empty).is_err());
+        assert!(serde_json::from_str::<ConstraintMask>(&overflow).is_err());
+    }
+}
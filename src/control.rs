@@ -0,0 +1,117 @@
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Stage of a running generation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GenerationStage {
+    /// The rooms of a new layout are chosen.
+    RoomLayout,
+    /// The layout is checked against the requirements.
+    Requirements,
+    /// Goals are selected and the boxes are placed on them.
+    EntityPlacement,
+    /// The boxes are pulled away from the goals to find the start state.
+    ReverseSearch,
+}
+
+/// Progress of a running generation, see [`GenerationControl::progress`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GenerationProgress {
+    pub stage: GenerationStage,
+    /// Attempts so far, including the current one.
+    pub attempts: u32,
+    /// States explored by the reverse search of the current attempt.
+    pub states_explored: usize,
+}
+
+/// Cancels a running generation. Clones share the same state, so the
+/// token can be cancelled from another thread (e.g. a UI thread).
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Cancellation and progress reporting of a generation
+/// (see [`try_generate_level_with`](crate::try_generate_level_with)).
+#[derive(Clone, Copy, Default)]
+pub struct GenerationControl<'a> {
+    /// The generation fails with [`GenerationError::Cancelled`](crate::GenerationError::Cancelled)
+    /// soon after the token is cancelled, also during a long reverse search.
+    pub cancellation: Option<&'a CancellationToken>,
+    /// Called when a stage begins and regularly during the reverse search.
+    pub progress: Option<&'a dyn Fn(&GenerationProgress)>,
+}
+
+impl fmt::Debug for GenerationControl<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GenerationControl")
+            .field("cancellation", &self.cancellation)
+            .field("progress", &self.progress.map(|_| "Fn"))
+            .finish()
+    }
+}
+
+impl GenerationControl<'_> {
+    pub(crate) fn is_cancelled(&self) -> bool {
+        self.cancellation.is_some_and(CancellationToken::is_cancelled)
+    }
+
+    pub(crate) fn report(&self, stage: GenerationStage, attempts: u32, states_explored: usize) {
+        if let Some(progress) = self.progress {
+            progress(&GenerationProgress {
+                stage,
+                attempts,
+                states_explored,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use super::*;
+    use crate::config::GenerationConfig;
+    use crate::level::{try_generate_level_with, GenerationError};
+
+    #[test]
+    fn report_progress_and_cancel() {
+        let mut config = GenerationConfig::new(2, 2, 1);
+        config.seed = Some(7);
+        let stages = RefCell::new(Vec::new());
+        let progress = |progress: &GenerationProgress| stages.borrow_mut().push(progress.stage);
+        let token = CancellationToken::new();
+        let mut control = GenerationControl {
+            cancellation: Some(&token),
+            progress: Some(&progress),
+        };
+
+        assert!(try_generate_level_with(&config, &control).is_ok());
+        assert_eq!(stages.borrow().first(), Some(&GenerationStage::RoomLayout));
+        assert!(stages.borrow().contains(&GenerationStage::EntityPlacement));
+        assert_eq!(stages.borrow().last(), Some(&GenerationStage::ReverseSearch));
+
+        token.clone().cancel();
+        control.progress = None;
+        assert_eq!(
+            try_generate_level_with(&config, &control),
+            Err(GenerationError::Cancelled { attempts: 0 })
+        );
+    }
+}
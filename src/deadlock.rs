@@ -0,0 +1,221 @@
+use std::collections::VecDeque;
+
+use ndarray::Array2;
+
+use crate::direction::Direction;
+use crate::level::Level;
+use crate::solver::{box_indices, Board};
+
+/// Calculate the simple dead squares of the level. A square is dead if a box
+/// on it can never be pushed to any goal, regardless of the other boxes.
+///
+/// The squares are found by pulling a box from every goal to all reachable
+/// positions. All squares inside the level that are never reached are dead.
+pub fn dead_squares(level: &Level) -> Array2<bool> {
+    let goals = level.indexed_iter().filter(|(_, &cell)| cell.is_goal()).map(|(position, _)| position);
+    let distances = pull_distances(level, goals);
+
+    Array2::from_shape_fn(level.dim(), |(y, x)| level[[y, x]].is_inside() && distances[[y, x]].is_none())
+}
+
+/// Calculate the minimum number of pushes of a box from every square to the
+/// nearest of the targets (y, x), regardless of the other boxes. Every solution
+/// pushes a box at least this often. `None` if the box cannot reach any target.
+///
+/// The distances are found by pulling a box from the targets (breadth first).
+pub(crate) fn pull_distances(
+    level: &Level,
+    targets: impl IntoIterator<Item = (usize, usize)>,
+) -> Array2<Option<u32>> {
+    let (height, width) = level.dim();
+    let mut distances = Array2::from_elem(level.dim(), None);
+    let mut queue = VecDeque::new();
+    for (y, x) in targets {
+        if distances[[y, x]].is_none() {
+            distances[[y, x]] = Some(0);
+            queue.push_back((x, y));
+        }
+    }
+
+    while let Some((x, y)) = queue.pop_front() {
+        let distance = distances[[y, x]].unwrap_or_default();
+
+        for direction in Direction::iterator() {
+            // the box is pulled to the next position, the player steps one further.
+            let next = direction.make_move(x, y, width, height);
+            let player = next.and_then(|(x, y)| direction.make_move(x, y, width, height));
+            if let (Some((next_x, next_y)), Some((player_x, player_y))) = (next, player) {
+                if level[[next_y, next_x]].is_inside()
+                    && level[[player_y, player_x]].is_inside()
+                    && distances[[next_y, next_x]].is_none()
+                {
+                    distances[[next_y, next_x]] = Some(distance + 1);
+                    queue.push_back((next_x, next_y));
+                }
+            }
+        }
+    }
+
+    distances
+}
+
+/// Find all boxes (y, x) that can never be moved again, because they are blocked
+/// by walls, dead squares or other frozen boxes on both axes.
+/// The level is in a freeze deadlock if any of these boxes is not on a goal.
+pub fn frozen_boxes(level: &Level) -> Vec<(usize, usize)> {
+    let board = Board::new(level);
+    let boxes = board.box_mask(&box_indices(level));
+
+    box_indices(level)
+        .into_iter()
+        .filter(|&position| is_frozen(&board, &boxes, position, &mut Frozen::new(boxes.len())))
+        .map(|position| board.position(position))
+        .collect()
+}
+
+/// Find all 2x2 blocks of boxes and walls that contain at least one box
+/// that is not on a goal. Returns the top left position (y, x) of the blocks.
+pub fn square_deadlocks(level: &Level) -> Vec<(usize, usize)> {
+    let (height, width) = level.dim();
+    if height < 2 || width < 2 {
+        return Vec::new();
+    }
+
+    level
+        .windows((2, 2))
+        .into_iter()
+        .zip((0..height - 1).flat_map(|y| (0..width - 1).map(move |x| (y, x))))
+        .filter(|(block, _)| {
+            block.iter().all(|cell| cell.is_box() || !cell.is_inside())
+                && block.iter().any(|cell| cell.is_box() && !cell.is_goal())
+        })
+        .map(|(_, position)| position)
+        .collect()
+}
+
+/// Check if the level is in a deadlock. This is the case if a box stands on
+/// a dead square, a frozen box is not on a goal, or a 2x2 block of boxes and
+/// walls contains a box that is not on a goal.
+pub fn is_deadlocked(level: &Level) -> bool {
+    let dead = dead_squares(level);
+    let board = Board::new(level);
+    let boxes = box_indices(level);
+    let mask = board.box_mask(&boxes);
+
+    boxes.iter().any(|&position| dead[board.position(position)])
+        || boxes.iter().any(|&position| is_freeze_deadlock(&board, &mask, position))
+        || !square_deadlocks(level).is_empty()
+}
+
+/// Check if the box on the position is frozen and it, or one of the
+/// boxes that freeze it, is not on a goal.
+pub(crate) fn is_freeze_deadlock(board: &Board, boxes: &[bool], position: usize) -> bool {
+    let mut frozen = Frozen::new(boxes.len());
+
+    is_frozen(board, boxes, position, &mut frozen)
+        && frozen.stack.iter().any(|&position| !board.is_goal(position))
+}
+
+/// Boxes that are assumed to be frozen during a check. They are treated as walls
+/// to avoid endless recursion, and removed again if the assumption is wrong.
+struct Frozen {
+    marked: Vec<bool>,
+    stack: Vec<usize>,
+}
+
+impl Frozen {
+    fn new(size: usize) -> Self {
+        Self {
+            marked: vec![false; size],
+            stack: Vec::new(),
+        }
+    }
+}
+
+/// Check if the box can never be moved again.
+fn is_frozen(board: &Board, boxes: &[bool], position: usize, frozen: &mut Frozen) -> bool {
+    let length = frozen.stack.len();
+    frozen.marked[position] = true;
+    frozen.stack.push(position);
+
+    let result = is_blocked(board, boxes, position, (Direction::Left, Direction::Right), frozen)
+        && is_blocked(board, boxes, position, (Direction::Up, Direction::Down), frozen);
+
+    if !result {
+        for position in frozen.stack.drain(length..) {
+            frozen.marked[position] = false;
+        }
+    }
+
+    result
+}
+
+/// Check if the box can not be pushed along the axis of the two directions.
+fn is_blocked(
+    board: &Board,
+    boxes: &[bool],
+    position: usize,
+    (first, second): (Direction, Direction),
+    frozen: &mut Frozen,
+) -> bool {
+    let first = board.neighbor(position, first);
+    let second = board.neighbor(position, second);
+    let (Some(first), Some(second)) = (first, second) else {
+        return true;
+    };
+
+    if board.is_wall(first) || board.is_wall(second) {
+        return true;
+    }
+
+    if board.is_dead(first) && board.is_dead(second) {
+        return true;
+    }
+
+    [first, second].into_iter().any(|neighbor| {
+        boxes[neighbor] && (frozen.marked[neighbor] || is_frozen(board, boxes, neighbor, frozen))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level::parse_level;
+
+    #[test]
+    fn dead_squares_in_corners() {
+        let level = parse_level("#####\n#   #\n# . #\n#@  #\n#####").unwrap();
+
+        let dead = dead_squares(&level);
+
+        assert!(dead[[1, 1]]);
+        assert!(dead[[3, 3]]);
+        assert!(!dead[[2, 2]]);
+        assert!(!dead[[0, 0]]);
+    }
+
+    #[test]
+    fn frozen_boxes_along_wall() {
+        let level = parse_level("######\n#$$  #\n#.@. #\n######").unwrap();
+
+        assert_eq!(frozen_boxes(&level), vec![(1, 1), (1, 2)]);
+        assert!(is_deadlocked(&level));
+    }
+
+    #[test]
+    fn frozen_boxes_on_goals_are_no_deadlock() {
+        let level = parse_level("#####\n#**@#\n#   #\n#####").unwrap();
+
+        assert_eq!(frozen_boxes(&level).len(), 2);
+        assert!(square_deadlocks(&level).is_empty());
+        assert!(!is_deadlocked(&level));
+    }
+
+    #[test]
+    fn square_of_boxes() {
+        let level = parse_level("######\n#    #\n# $$ #\n# $$ #\n#....#\n#@   #\n######").unwrap();
+
+        assert_eq!(square_deadlocks(&level), vec![(2, 2)]);
+        assert!(is_deadlocked(&level));
+    }
+}
@@ -0,0 +1,74 @@
+/// Direction in which the player moves or pushes a box.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Direction {
+    /// Returns the position (x, y) next to the given position in this direction,
+    /// or `None` if the position would be outside of the level.
+    pub fn make_move(&self, x: usize, y: usize, width: usize, height: usize) -> Option<(usize, usize)> {
+        match self {
+            Self::Up if y > 0 => Some((x, y - 1)),
+            Self::Down if y < (height - 1) => Some((x, y + 1)),
+            Self::Left if x > 0 => Some((x - 1, y)),
+            Self::Right if x < (width - 1) => Some((x + 1, y)),
+            _ => None,
+        }
+    }
+
+    pub fn iterator() -> impl Iterator<Item=Direction> {
+        [
+            Direction::Up,
+            Direction::Down,
+            Direction::Left,
+            Direction::Right,
+        ]
+            .iter()
+            .copied()
+    }
+
+    pub fn opposite(self) -> Self {
+        match self {
+            Self::Up => Self::Down,
+            Self::Down => Self::Up,
+            Self::Left => Self::Right,
+            Self::Right => Self::Left,
+        }
+    }
+
+    /// Returns the character of the direction in the LURD notation.
+    /// Moves are lowercase, pushes are uppercase.
+    pub fn to_char(self, push: bool) -> char {
+        let char = match self {
+            Self::Up => 'u',
+            Self::Down => 'd',
+            Self::Left => 'l',
+            Self::Right => 'r',
+        };
+
+        if push {
+            char.to_ascii_uppercase()
+        } else {
+            char
+        }
+    }
+
+    /// Parses a character in the LURD notation. Returns the direction
+    /// and whether the character is a push (uppercase).
+    pub fn from_char(char: char) -> Option<(Self, bool)> {
+        let direction = match char.to_ascii_lowercase() {
+            'u' => Self::Up,
+            'd' => Self::Down,
+            'l' => Self::Left,
+            'r' => Self::Right,
+            _ => return None,
+        };
+
+        Some((direction, char.is_ascii_uppercase()))
+    }
+}
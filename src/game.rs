@@ -0,0 +1,399 @@
+use std::error::Error;
+use std::fmt;
+
+use crate::cell::Cell;
+use crate::direction::Direction;
+use crate::level::{decode_level, encode_level, Level, ParseLevelError};
+use crate::solver::{find_player, SolveError, Step};
+
+/// Version of the session format that is written by [`Game::save`].
+const SESSION_VERSION: &str = "sokoban-session/1";
+
+/// Error that occurs when a game cannot be created or a move cannot be replayed.
+#[derive(Clone, Debug, PartialEq)]
+pub enum GameError {
+    /// The level does not contain exactly one player
+    /// ([`SolveError::NoPlayer`] or [`SolveError::MultiplePlayers`]).
+    InvalidLevel(SolveError),
+    /// The character at the index is not part of the LURD notation.
+    InvalidMove { index: usize, char: char },
+    /// The move at the index is not possible in the level, or the
+    /// notation of the push (upper or lower case) does not match.
+    IllegalMove { index: usize, char: char },
+}
+
+impl fmt::Display for GameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidLevel(error) => write!(f, "{}", error),
+            Self::InvalidMove { index, char } => {
+                write!(f, "invalid move '{}' at index {}", char, index)
+            }
+            Self::IllegalMove { index, char } => {
+                write!(f, "move '{}' at index {} is not possible", char, index)
+            }
+        }
+    }
+}
+
+impl Error for GameError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::InvalidLevel(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<SolveError> for GameError {
+    fn from(error: SolveError) -> Self {
+        Self::InvalidLevel(error)
+    }
+}
+
+/// Error that occurs when a saved session cannot be restored.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SessionError {
+    /// The session was saved with an unknown version of the format.
+    UnsupportedVersion(String),
+    /// The session does not have the expected parts.
+    Malformed,
+    /// The checksum does not match the content, the session is corrupted.
+    ChecksumMismatch,
+    /// The level of the session cannot be decoded.
+    InvalidLevel(ParseLevelError),
+    /// The moves of the session do not match the level.
+    InvalidMoves(GameError),
+}
+
+impl fmt::Display for SessionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnsupportedVersion(version) => write!(f, "unsupported session version '{}'", version),
+            Self::Malformed => write!(f, "the session is malformed"),
+            Self::ChecksumMismatch => write!(f, "the checksum of the session does not match"),
+            Self::InvalidLevel(error) => write!(f, "invalid level in session: {}", error),
+            Self::InvalidMoves(error) => write!(f, "invalid moves in session: {}", error),
+        }
+    }
+}
+
+impl Error for SessionError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::InvalidLevel(error) => Some(error),
+            Self::InvalidMoves(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// A playable game of a level with undo and redo.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Game {
+    initial: Level,
+    level: Level,
+    /// Position (y, x) of the player.
+    player: (usize, usize),
+    history: Vec<Step>,
+    redo: Vec<Step>,
+}
+
+impl Game {
+    /// Start a new game of the level. Special floors are played (and saved) as floors.
+    pub fn new(mut level: Level) -> Result<Self, GameError> {
+        let player = find_player(&level)?;
+        level.map_inplace(|cell| {
+            if *cell == Cell::SpecialFloor {
+                *cell = Cell::Floor;
+            }
+        });
+
+        Ok(Self {
+            initial: level.clone(),
+            level,
+            player,
+            history: Vec::new(),
+            redo: Vec::new(),
+        })
+    }
+
+    /// The current state of the level.
+    pub fn level(&self) -> &Level {
+        &self.level
+    }
+
+    /// The level at the start of the game.
+    pub fn initial_level(&self) -> &Level {
+        &self.initial
+    }
+
+    /// Position (y, x) of the player.
+    pub fn player(&self) -> (usize, usize) {
+        self.player
+    }
+
+    /// Returns true if all boxes are on goals.
+    pub fn is_solved(&self) -> bool {
+        self.level.iter().all(|cell| !cell.is_box() || cell.is_goal())
+    }
+
+    pub fn move_count(&self) -> usize {
+        self.history.len()
+    }
+
+    pub fn push_count(&self) -> usize {
+        self.history.iter().filter(|(_, push)| *push).count()
+    }
+
+    /// All moves of the game in the LURD notation, pushes are uppercase.
+    pub fn moves(&self) -> String {
+        self.history.iter().map(|(direction, push)| direction.to_char(*push)).collect()
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.history.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Move the player in the direction and push a box if there is one.
+    /// Returns false if the move is not possible. A move clears the redo history.
+    pub fn make_move(&mut self, direction: Direction) -> bool {
+        match self.step(direction) {
+            Some(step) => {
+                self.history.push(step);
+                self.redo.clear();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Revert the last move. Returns false if there is no move to undo.
+    pub fn undo(&mut self) -> bool {
+        let Some((direction, push)) = self.history.pop() else {
+            return false;
+        };
+
+        let (height, width) = self.level.dim();
+        let (y, x) = self.player;
+        let (previous_x, previous_y) = direction
+            .opposite()
+            .make_move(x, y, width, height)
+            .expect("the previous position is inside the level");
+
+        if push {
+            let (box_x, box_y) = direction
+                .make_move(x, y, width, height)
+                .expect("the pushed box is inside the level");
+            self.level[[box_y, box_x]] = self.level[[box_y, box_x]].without_entity();
+            self.level[[y, x]] = self.level[[y, x]].without_entity().with_box();
+        } else {
+            self.level[[y, x]] = self.level[[y, x]].without_entity();
+        }
+
+        self.level[[previous_y, previous_x]] = self.level[[previous_y, previous_x]].with_player();
+        self.player = (previous_y, previous_x);
+        self.redo.push((direction, push));
+
+        true
+    }
+
+    /// Repeat the last undone move. Returns false if there is no move to redo.
+    pub fn redo(&mut self) -> bool {
+        let Some((direction, _)) = self.redo.pop() else {
+            return false;
+        };
+
+        let step = self.step(direction).expect("an undone move is possible again");
+        self.history.push(step);
+
+        true
+    }
+
+    /// Reset the level to the start. The moves can be redone afterwards.
+    pub fn restart(&mut self) {
+        while self.undo() {}
+    }
+
+    /// Replay moves in the LURD notation. Pushes must be uppercase
+    /// and moves lowercase. Stops at the first invalid or illegal move.
+    pub fn apply_moves(&mut self, moves: &str) -> Result<(), GameError> {
+        for (index, char) in moves.chars().enumerate() {
+            let (direction, push) =
+                Direction::from_char(char).ok_or(GameError::InvalidMove { index, char })?;
+
+            let step = self.step(direction).ok_or(GameError::IllegalMove { index, char })?;
+            self.history.push(step);
+            self.redo.clear();
+
+            if step.1 != push {
+                self.undo();
+                self.redo.clear();
+                return Err(GameError::IllegalMove { index, char });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Save the session in a compact, versioned text format. The session contains
+    /// the initial level (encoded by [`encode_level`]), the moves and the redo history.
+    pub fn save(&self) -> String {
+        let redo = self
+            .redo
+            .iter()
+            .rev()
+            .map(|(direction, push)| direction.to_char(*push))
+            .collect::<String>();
+        let content = format!(
+            "{};{};{};{}",
+            SESSION_VERSION,
+            encode_level(&self.initial),
+            self.moves(),
+            redo
+        );
+        let checksum = checksum(&content);
+
+        format!("{};{:08x}", content, checksum)
+    }
+
+    /// Restore a session that was saved with [`Game::save`].
+    pub fn restore(session: &str) -> Result<Self, SessionError> {
+        let session = session.trim();
+        let version = session.split(';').next().unwrap_or_default();
+        if version != SESSION_VERSION {
+            return Err(SessionError::UnsupportedVersion(version.to_string()));
+        }
+
+        let parts = session.split(';').collect::<Vec<_>>();
+        let [_, level, moves, redo, expected] = parts[..] else {
+            return Err(SessionError::Malformed);
+        };
+
+        let content = &session[..session.len() - expected.len() - 1];
+        if u32::from_str_radix(expected, 16).ok() != Some(checksum(content)) {
+            return Err(SessionError::ChecksumMismatch);
+        }
+
+        let level = decode_level(level).map_err(SessionError::InvalidLevel)?;
+        let mut game = Self::new(level).map_err(SessionError::InvalidMoves)?;
+
+        // apply the redo history to validate it, then undo it again.
+        let history = moves.chars().count();
+        game.apply_moves(&format!("{}{}", moves, redo)).map_err(|error| {
+            SessionError::InvalidMoves(match error {
+                GameError::InvalidMove { index, char } if index >= history => {
+                    GameError::InvalidMove { index: index - history, char }
+                }
+                GameError::IllegalMove { index, char } if index >= history => {
+                    GameError::IllegalMove { index: index - history, char }
+                }
+                error => error,
+            })
+        })?;
+        for _ in 0..redo.chars().count() {
+            game.undo();
+        }
+
+        Ok(game)
+    }
+
+    /// Perform the move without touching the history.
+    fn step(&mut self, direction: Direction) -> Option<Step> {
+        let (height, width) = self.level.dim();
+        let (y, x) = self.player;
+        let (next_x, next_y) = direction.make_move(x, y, width, height)?;
+        let next = self.level[[next_y, next_x]];
+
+        let push = if next.is_box() {
+            let (box_x, box_y) = direction.make_move(next_x, next_y, width, height)?;
+            if !self.level[[box_y, box_x]].is_walkable() {
+                return None;
+            }
+
+            self.level[[box_y, box_x]] = self.level[[box_y, box_x]].with_box();
+            true
+        } else if next.is_walkable() {
+            false
+        } else {
+            return None;
+        };
+
+        self.level[[y, x]] = self.level[[y, x]].without_entity();
+        self.level[[next_y, next_x]] = next.without_entity().with_player();
+        self.player = (next_y, next_x);
+
+        Some((direction, push))
+    }
+}
+
+/// FNV-1a hash of the content to detect corrupted sessions.
+fn checksum(content: &str) -> u32 {
+    content.bytes().fold(0x811c9dc5, |hash, byte| (hash ^ byte as u32).wrapping_mul(0x01000193))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GenerationConfig;
+    use crate::level::{parse_level, try_generate_level};
+
+    fn game() -> Game {
+        Game::new(parse_level("#######\n#@ $ .#\n#######").unwrap()).unwrap()
+    }
+
+    #[test]
+    fn undo_and_redo_moves() {
+        let mut game = game();
+
+        assert!(game.make_move(Direction::Right));
+        assert!(game.make_move(Direction::Right));
+        assert!(!game.make_move(Direction::Up));
+        assert_eq!(game.moves(), "rR");
+
+        assert!(game.undo());
+        assert_eq!(game.level(), &parse_level("#######\n# @$ .#\n#######").unwrap());
+        assert!(game.redo());
+        assert!(game.make_move(Direction::Right));
+        assert!(game.is_solved());
+
+        let level = parse_level("####\n#@@#\n####").unwrap();
+        assert_eq!(Game::new(level), Err(GameError::InvalidLevel(SolveError::MultiplePlayers)));
+    }
+
+    #[test]
+    fn restore_saved_session() {
+        let mut config = GenerationConfig::new(3, 3, 2);
+        config.seed = Some(1);
+        let mut game = Game::new(try_generate_level(&config).unwrap()).unwrap();
+        for direction in Direction::iterator() {
+            game.make_move(direction);
+        }
+        game.undo();
+
+        let restored = Game::restore(&game.save()).unwrap();
+
+        assert_eq!(restored, game);
+    }
+
+    #[test]
+    fn restore_corrupted_session() {
+        let mut game = game();
+        game.apply_moves("rR").unwrap();
+        let session = game.save();
+
+        assert_eq!(
+            Game::restore(&session.replace("rR", "rr")),
+            Err(SessionError::ChecksumMismatch)
+        );
+        assert_eq!(
+            Game::restore(&session.replace("/1", "/9")),
+            Err(SessionError::UnsupportedVersion("sokoban-session/9".to_string()))
+        );
+        assert_eq!(Game::restore("sokoban-session/1;7#"), Err(SessionError::Malformed));
+    }
+}
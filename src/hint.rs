@@ -0,0 +1,117 @@
+use crate::cell::Cell;
+use crate::deadlock::is_deadlocked;
+use crate::direction::Direction;
+use crate::level::{is_accessible, Level};
+use crate::solver::{solve, Metric, Push, SolveError, SolverLimits};
+
+/// The next push towards a push optimal solution.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Hint {
+    /// The moves of the player to the box, followed by the push (LURD notation).
+    pub moves: String,
+    pub push: Push,
+    /// Number of pushes that are left to solve the level, including this push.
+    pub remaining_pushes: usize,
+}
+
+/// Calculate the next push for a level in any state of the game.
+/// Returns `Ok(None)` if the level is already solved and
+/// [`SolveError::Unsolvable`] if the level cannot be solved from this state.
+///
+/// The search is limited by the given limits, a `max_duration`
+/// keeps the response time of the hint bounded.
+pub fn hint(level: &Level, limits: &SolverLimits) -> Result<Option<Hint>, SolveError> {
+    // without a search, a level is lost if it is deadlocked or the player cannot push any box.
+    let solved = !level.iter().any(|&cell| cell == Cell::Box);
+    let single_player = level.iter().filter(|cell| cell.is_player()).count() == 1;
+    if is_deadlocked(level) || (!solved && single_player && possible_pushes(level).is_empty()) {
+        return Err(SolveError::Unsolvable { states_explored: 0 });
+    }
+
+    let solution = solve(level, Metric::Pushes, limits)?;
+    let Some(&push) = solution.pushes.first() else {
+        return Ok(None);
+    };
+    debug_assert!(possible_pushes(level).contains(&push));
+
+    let push_index = solution
+        .moves
+        .find(|char: char| char.is_ascii_uppercase())
+        .expect("a solution with pushes contains an uppercase move");
+
+    Ok(Some(Hint {
+        moves: solution.moves[..=push_index].to_string(),
+        push,
+        remaining_pushes: solution.push_count(),
+    }))
+}
+
+/// Find the pushes the player can make from the current state: the player can walk
+/// to the cell behind the box and the cell in front of the box is free.
+fn possible_pushes(level: &Level) -> Vec<Push> {
+    let (height, width) = level.dim();
+    let Some(((player_y, player_x), _)) = level.indexed_iter().find(|(_, cell)| cell.is_player()) else {
+        return Vec::new();
+    };
+
+    // the player walks on the level without itself.
+    let mut floors = level.clone();
+    floors[[player_y, player_x]] = level[[player_y, player_x]].without_entity();
+
+    level
+        .indexed_iter()
+        .filter(|(_, cell)| cell.is_box())
+        .flat_map(|(from, _)| Direction::iterator().map(move |direction| Push { from, direction }))
+        .filter(|push| {
+            let (y, x) = push.from;
+            let target = push.direction.make_move(x, y, width, height);
+            let behind = push.direction.opposite().make_move(x, y, width, height);
+
+            match (target, behind) {
+                (Some((target_x, target_y)), Some(behind)) => {
+                    floors[[target_y, target_x]].is_walkable() && is_accessible(&floors, (player_x, player_y), behind)
+                }
+                _ => false,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level::parse_level;
+
+    #[test]
+    fn hint_solved_and_lost_levels() {
+        let limits = SolverLimits::default();
+
+        assert_eq!(hint(&parse_level("####\n#@*#\n####").unwrap(), &limits), Ok(None));
+        // the box is in a corner.
+        assert_eq!(
+            hint(&parse_level("#####\n#$  #\n# @.#\n#####").unwrap(), &limits),
+            Err(SolveError::Unsolvable { states_explored: 0 })
+        );
+        // the player cannot reach the box.
+        assert_eq!(
+            hint(&parse_level("#######\n#@# $.#\n#######").unwrap(), &limits),
+            Err(SolveError::Unsolvable { states_explored: 0 })
+        );
+    }
+
+    #[test]
+    fn hint_first_push_of_solution() {
+        let level = parse_level("######\n#.$ @#\n#.$  #\n######").unwrap();
+        let limits = SolverLimits::default();
+        let solution = solve(&level, Metric::Pushes, &limits).unwrap();
+
+        let hint = hint(&level, &limits).unwrap().unwrap();
+
+        assert_eq!(hint.push, solution.pushes[0]);
+        assert!(solution.moves.starts_with(&hint.moves));
+        assert!(hint.moves.ends_with(|char: char| char.is_ascii_uppercase()));
+        assert_eq!(hint.remaining_pushes, solution.push_count());
+        assert!(possible_pushes(&level).contains(&hint.push));
+    }
+}
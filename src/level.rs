@@ -1,6 +1,18 @@
+use std::error::Error;
+use std::fmt;
+use std::time::Instant;
+
 use ndarray::{Array2, s};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
 
+use crate::analysis::analyze_with_limits;
 use crate::cell::Cell;
+use crate::config::GenerationConfig;
+use crate::constraints::ConstraintError;
+use crate::control::{GenerationControl, GenerationStage};
+use crate::stats::GenerationStats;
+use crate::solver::{solve, Metric};
 
 /// Representation of a level in sokoban.
 pub type Level = Array2<Cell>;
@@ -9,32 +21,290 @@ pub type Level = Array2<Cell>;
 /// Height and width are the number of rooms in the level.
 /// A room is 3x3 cells/fields.
 pub fn generate_level(height: u8, width: u8, boxes: u8) -> Level {
-    let rooms = loop {
-        let rooms = generation::generate_rooms(height.into(), width.into());
-        if requirements::level_meets_requirements(&rooms, boxes) {
-            break rooms;
+    try_generate_level(&GenerationConfig::new(height, width, boxes))
+        .expect("generation without attempt limit never fails")
+}
+
+/// Error that occurs when no level can be generated for a configuration.
+#[derive(Clone, Debug, PartialEq)]
+pub enum GenerationError {
+    /// No level that meets the configuration was found within the attempt limit.
+    AttemptsExhausted { attempts: u32 },
+    /// No level that meets the configuration was found within the maximum duration.
+    TimeLimitReached { attempts: u32 },
+    /// The generation was cancelled (see [`GenerationControl`]).
+    Cancelled { attempts: u32 },
+    /// The constraints of the configuration are invalid or contradictory.
+    Constraints(ConstraintError),
+}
+
+impl fmt::Display for GenerationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::AttemptsExhausted { attempts } => {
+                write!(f, "no level found within {} attempts", attempts)
+            }
+            Self::TimeLimitReached { attempts } => {
+                write!(f, "no level found within the time limit ({} attempts)", attempts)
+            }
+            Self::Cancelled { attempts } => {
+                write!(f, "the generation was cancelled after {} attempts", attempts)
+            }
+            Self::Constraints(error) => write!(f, "invalid constraints: {}", error),
         }
+    }
+}
+
+impl Error for GenerationError {}
+
+/// Generate a new level for the given configuration.
+/// Layouts and start states that do not meet the required pushes or
+/// difficulty are rejected. Fails if no level is found within the
+/// maximum number of attempts or duration.
+pub fn try_generate_level(config: &GenerationConfig) -> Result<Level, GenerationError> {
+    try_generate_level_with(config, &GenerationControl::default())
+}
+
+/// Generate a new level like [`try_generate_level`], report the progress
+/// and stop when the generation is cancelled.
+pub fn try_generate_level_with(config: &GenerationConfig, control: &GenerationControl) -> Result<Level, GenerationError> {
+    try_generate_level_with_stats(config, control).0
+}
+
+/// Generate a new level like [`try_generate_level_with`] and return the
+/// statistics of the generation, e.g. which requirements rejected the layouts.
+pub fn try_generate_level_with_stats(
+    config: &GenerationConfig,
+    control: &GenerationControl,
+) -> (Result<Level, GenerationError>, GenerationStats) {
+    let mut stats = GenerationStats::default();
+    let result = generate_with_stats(config, control, &mut stats);
+
+    (result, stats)
+}
+
+/// Number of failed placements of the entities after which a layout is dropped.
+const PLACEMENTS_PER_LAYOUT: u32 = 10;
+
+/// Number of layouts in a row that fail because of the constraints,
+/// after which the constraints are considered unsatisfiable.
+const MAX_CONSTRAINED_LAYOUTS: u32 = 100;
+
+fn generate_with_stats(
+    config: &GenerationConfig,
+    control: &GenerationControl,
+    stats: &mut GenerationStats,
+) -> Result<Level, GenerationError> {
+    if let Some(constraints) = &config.constraints {
+        let dim = (usize::from(config.height) * 3, usize::from(config.width) * 3);
+        constraints
+            .validate(dim, config.boxes)
+            .and_then(|_| constraints.validate_rooms())
+            .and_then(|_| constraints.validate_requirements(config.boxes))
+            .map_err(GenerationError::Constraints)?;
+    }
+
+    let start = Instant::now();
+    let mut rng = match config.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
     };
+    let mut attempts = 0;
+    // layouts in a row that failed because of the constraints.
+    let mut constrained_failures = 0;
+    // the layout of the last attempt and its failed placements.
+    let mut rooms = None;
+
+    loop {
+        if control.is_cancelled() {
+            return Err(GenerationError::Cancelled { attempts });
+        }
+        if config.max_attempts.is_some_and(|max| attempts >= max) {
+            return Err(GenerationError::AttemptsExhausted { attempts });
+        }
+        if config.max_duration.is_some_and(|max| start.elapsed() >= max) {
+            return Err(GenerationError::TimeLimitReached { attempts });
+        }
+
+        attempts += 1;
+        stats.attempts = attempts;
+        let (layout, failed_placements) = match rooms.take() {
+            Some(rooms) => rooms,
+            None => {
+                control.report(GenerationStage::RoomLayout, attempts, 0);
+                let height = config.height.into();
+                let width = config.width.into();
+                let constraints = config.constraints.as_ref();
+                let Some(layout) = generation::generate_rooms_with_stats(height, width, &mut rng, constraints, stats) else {
+                    stats.constraint_failures += 1;
+                    constrained_failures += 1;
+                    if constrained_failures >= MAX_CONSTRAINED_LAYOUTS {
+                        let error = ConstraintError::Unsatisfiable { layouts: constrained_failures };
+                        return Err(GenerationError::Constraints(error));
+                    }
+                    continue;
+                };
+                constrained_failures = 0;
+                stats.layouts += 1;
+                control.report(GenerationStage::Requirements, attempts, 0);
+                if let Some(requirement) = requirements::failed_requirement(&layout, config.boxes) {
+                    requirement.count(&mut stats.rejections);
+                    continue;
+                }
+                (layout, 0)
+            }
+        };
 
-    let level = loop {
-        let rooms = rooms.clone();
         // Place entities (goals, boxes, player) in the level.
         // If there is no backtrack map for the box positions, retry.
-        if let Some(level) = entities::place_entities(rooms, boxes.into()) {
-            break level;
+        control.report(GenerationStage::EntityPlacement, attempts, 0);
+        // The search stops early when the generation is cancelled or runs out of time.
+        let search = |states_explored| {
+            control.report(GenerationStage::ReverseSearch, attempts, states_explored);
+            !control.is_cancelled() && config.max_duration.is_none_or(|max| start.elapsed() < max)
+        };
+        let Some(level) = entities::place_entities_with(layout.clone(), config, &mut rng, &search, stats) else {
+            stats.placement_failures += 1;
+            // other goals may work, but some layouts have no start state at all.
+            if failed_placements + 1 < PLACEMENTS_PER_LAYOUT {
+                rooms = Some((layout, failed_placements + 1));
+            }
+            continue;
+        };
+
+        let level = frame_level(&level);
+        if meets_targets(&level, config) {
+            return Ok(level);
         }
+        stats.target_rejections += 1;
+    }
+}
+
+/// Default number of attempts of [`place_entities_on_layout`] if the
+/// configuration has no attempt limit.
+const LAYOUT_ATTEMPTS: u32 = 100;
+
+/// Error that occurs when the entities cannot be placed on a layout.
+#[derive(Clone, Debug, PartialEq)]
+pub enum LayoutError {
+    /// The layout contains other cells than walls, floors and empty cells.
+    InvalidCell { row: usize, column: usize, cell: Cell },
+    /// Not all floors of the layout are connected.
+    NotConnected,
+    /// The layout has not enough cells for the goals of the boxes.
+    NotEnoughGoalPlaces { boxes: u8, goal_places: usize },
+    /// No start state that meets the configuration was found within the attempts.
+    NoStartState { attempts: u32 },
+    /// The constraints of the configuration are invalid or contradict the layout.
+    Constraints(ConstraintError),
+}
+
+impl fmt::Display for LayoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidCell { row, column, cell } => write!(
+                f,
+                "the layout contains '{}' in row {}, column {}, only walls and floors are allowed",
+                cell.to_char(),
+                row,
+                column
+            ),
+            Self::NotConnected => write!(f, "the floors of the layout are not connected"),
+            Self::NotEnoughGoalPlaces { boxes, goal_places } => write!(
+                f,
+                "the layout cannot hold {} boxes, it has only {} places for goals",
+                boxes, goal_places
+            ),
+            Self::NoStartState { attempts } => {
+                write!(f, "no start state found within {} attempts", attempts)
+            }
+            Self::Constraints(error) => write!(f, "invalid constraints: {}", error),
+        }
+    }
+}
+
+impl Error for LayoutError {}
+
+/// Place goals, boxes and the player on a layout of walls and floors, e.g. a
+/// layout that is drawn by hand and read with [`parse_level`]. The layout must
+/// be connected and have enough places for the goals (see [`Requirement`]).
+///
+/// The goals are selected and the start state is searched like in
+/// [`place_entities`], until a start state meets the configuration. The
+/// number of boxes, the seed, the scorer, the targets and the constraints (with
+/// the size of the layout, see [`ConstraintMask::with_size`](crate::ConstraintMask::with_size))
+/// of the configuration are used. Without an attempt limit, up to 100 goal
+/// selections are tried.
+/// The layout is not framed with walls.
+pub fn place_entities_on_layout(layout: &Level, config: &GenerationConfig) -> Result<Level, LayoutError> {
+    if let Some(((row, column), &cell)) = layout
+        .indexed_iter()
+        .find(|(_, cell)| !matches!(cell, Cell::Wall | Cell::Floor | Cell::SpecialFloor | Cell::Empty))
+    {
+        return Err(LayoutError::InvalidCell { row, column, cell });
+    }
+
+    if let Some(constraints) = &config.constraints {
+        constraints
+            .validate(layout.dim(), config.boxes)
+            .and_then(|_| constraints.validate_layout(layout))
+            .map_err(LayoutError::Constraints)?;
+    }
+
+    if !Requirement::EnoughGoalPlaces.is_met(layout, config.boxes) {
+        return Err(LayoutError::NotEnoughGoalPlaces {
+            boxes: config.boxes,
+            goal_places: entities::get_possible_goal_locations(layout).len(),
+        });
+    }
+    if !Requirement::Connectivity.is_met(layout, config.boxes) {
+        return Err(LayoutError::NotConnected);
+    }
+
+    let mut rng = match config.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
     };
+    let attempts = config.max_attempts.unwrap_or(LAYOUT_ATTEMPTS);
+    (0..attempts)
+        .filter_map(|_| place_entities(layout.clone(), config, &mut rng))
+        .find(|level| meets_targets(level, config))
+        .ok_or(LayoutError::NoStartState { attempts })
+}
 
-    let h_end = (height * 3) as usize;
-    let w_end = (width * 3) as usize;
+/// Surround the level with walls. This is the last stage of the generation,
+/// after [`place_entities`].
+pub fn frame_level(level: &Level) -> Level {
+    let (h_end, w_end) = level.dim();
 
     let mut framed_level = Array2::from_elem((h_end + 2, w_end + 2), Cell::Wall);
     let mut space = framed_level.slice_mut(s![1..=h_end, 1..=w_end]);
-    space.assign(&level);
+    space.assign(level);
 
     framed_level
 }
 
+/// Check the required pushes and difficulty of the configuration.
+fn meets_targets(level: &Level, config: &GenerationConfig) -> bool {
+    if let Some(difficulty) = &config.difficulty {
+        let stats = analyze_with_limits(level, &config.solver_limits);
+        let pushes = stats.push_optimal.map(|length| length.pushes);
+
+        return stats.difficulty().is_some_and(|score| difficulty.contains(&score))
+            && config
+                .pushes
+                .as_ref()
+                .is_none_or(|range| pushes.is_some_and(|pushes| range.contains(&pushes)));
+    }
+
+    if let Some(pushes) = &config.pushes {
+        return solve(level, Metric::Pushes, &config.solver_limits)
+            .is_ok_and(|solution| pushes.contains(&solution.push_count()));
+    }
+
+    true
+}
+
 /// Create the string representation of a level.
 /// This creates an exchangeable string that can be used
 /// to share the levels with others.
@@ -87,11 +357,54 @@ pub fn encode_level(level: &Level) -> String {
     encoding
 }
 
+/// Maximum number of rows and columns of a level that is parsed or decoded.
+pub const MAX_LEVEL_SIZE: usize = 1024;
+
+/// Decode a level from its string representation (see [`encode_level`]).
+/// The count of a run can be omitted if it is 1.
+pub fn decode_level(encoding: &str) -> Result<Level, ParseLevelError> {
+    let mut rows = Vec::new();
+
+    for (y, encoded_row) in encoding.split('|').enumerate() {
+        if y >= MAX_LEVEL_SIZE {
+            return Err(ParseLevelError::TooLarge);
+        }
+
+        let mut row = String::new();
+        let mut length = 0;
+        let mut count = String::new();
+
+        for (x, char) in encoded_row.chars().enumerate() {
+            if char.is_ascii_digit() {
+                count.push(char);
+                continue;
+            }
+
+            if Cell::from_char(char).is_none() {
+                return Err(ParseLevelError::InvalidCharacter { row: y, column: x, char });
+            }
+
+            // counts that do not fit into usize are too large as well.
+            let repeat = if count.is_empty() { 1 } else { count.parse().unwrap_or(usize::MAX) };
+            length = repeat.saturating_add(length);
+            if length > MAX_LEVEL_SIZE {
+                return Err(ParseLevelError::TooLarge);
+            }
+            row.extend(std::iter::repeat_n(char, repeat));
+            count.clear();
+        }
+
+        rows.push(row);
+    }
+
+    level_from_rows(&rows)
+}
+
 /// Print the level to a string with new-lines.
 /// The notation is from [JSoko Level Format](https://www.sokoban-online.de/sokoban/levell-format/).
 pub fn pretty_print_level(level: &Level) -> String {
     let (w, h) = level.dim();
-    let mut print = String::with_capacity((w * h + h).into());
+    let mut print = String::with_capacity(w * h + h);
 
     for row in level.rows() {
         for cell in row {
@@ -103,20 +416,192 @@ pub fn pretty_print_level(level: &Level) -> String {
     print
 }
 
+/// Error that occurs when a level cannot be parsed.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParseLevelError {
+    /// The text does not contain any rows.
+    Empty,
+    /// The text contains a character that is not part of the level format.
+    InvalidCharacter { row: usize, column: usize, char: char },
+    /// The level has more than [`MAX_LEVEL_SIZE`] rows or columns.
+    TooLarge,
+}
+
+impl fmt::Display for ParseLevelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "the level does not contain any rows"),
+            Self::InvalidCharacter { row, column, char } => write!(
+                f,
+                "invalid character '{}' in row {}, column {}",
+                char, row, column
+            ),
+            Self::TooLarge => write!(
+                f,
+                "the level has more than {} rows or columns",
+                MAX_LEVEL_SIZE
+            ),
+        }
+    }
+}
+
+impl Error for ParseLevelError {}
+
+/// Parse a level that is printed with new-lines (e.g. by [`pretty_print_level`]).
+/// The notation is from [JSoko Level Format](https://www.sokoban-online.de/sokoban/levell-format/).
+///
+/// Rows may have different lengths, missing cells are filled with empty cells.
+/// Floors that are reachable from the edge of the level without crossing a wall
+/// are outside of the level and become empty cells.
+pub fn parse_level(text: &str) -> Result<Level, ParseLevelError> {
+    let rows = text.lines().collect::<Vec<_>>();
+    level_from_rows(&rows)
+}
+
+/// Create a level from its rows. See [`parse_level`].
+pub(crate) fn level_from_rows<S: AsRef<str>>(rows: &[S]) -> Result<Level, ParseLevelError> {
+    level_from_rows_with(rows, Cell::from_char)
+}
+
+/// Create a level from its rows like [`level_from_rows`], with another mapping of the characters.
+pub(crate) fn level_from_rows_with<S: AsRef<str>>(
+    rows: &[S],
+    from_char: impl Fn(char) -> Option<Cell>,
+) -> Result<Level, ParseLevelError> {
+    let mut rows = rows.iter().map(|row| row.as_ref()).collect::<Vec<_>>();
+    while rows.last().is_some_and(|row| row.trim().is_empty()) {
+        rows.pop();
+    }
+
+    let height = rows.len();
+    let width = rows.iter().map(|row| row.chars().count()).max().unwrap_or(0);
+    if height == 0 || width == 0 {
+        return Err(ParseLevelError::Empty);
+    }
+    if height > MAX_LEVEL_SIZE || width > MAX_LEVEL_SIZE {
+        return Err(ParseLevelError::TooLarge);
+    }
+
+    let mut level = Array2::from_elem((height, width), Cell::Empty);
+    for (y, row) in rows.iter().enumerate() {
+        for (x, char) in row.chars().enumerate() {
+            level[[y, x]] = from_char(char).ok_or(ParseLevelError::InvalidCharacter {
+                row: y,
+                column: x,
+                char,
+            })?;
+        }
+    }
+
+    mark_outside_cells(&mut level);
+    Ok(level)
+}
+
+/// Turn all floors that are reachable from the edge of the level into empty cells.
+fn mark_outside_cells(level: &mut Level) {
+    let (height, width) = level.dim();
+    let mut visited = Array2::from_elem(level.dim(), false);
+    let mut stack = level
+        .indexed_iter()
+        .filter(|((y, x), _)| *y == 0 || *x == 0 || *y == height - 1 || *x == width - 1)
+        .map(|(index, _)| index)
+        .collect::<Vec<_>>();
+
+    while let Some((y, x)) = stack.pop() {
+        if visited[[y, x]] || !matches!(level[[y, x]], Cell::Floor | Cell::Empty) {
+            continue;
+        }
+
+        visited[[y, x]] = true;
+        level[[y, x]] = Cell::Empty;
+
+        if y > 0 {
+            stack.push((y - 1, x));
+        }
+        if y < height - 1 {
+            stack.push((y + 1, x));
+        }
+        if x > 0 {
+            stack.push((y, x - 1));
+        }
+        if x < width - 1 {
+            stack.push((y, x + 1));
+        }
+    }
+}
+
+pub use entities::place_entities;
+pub(crate) use entities::is_accessible;
+pub use generation::{generate_constrained_rooms, generate_rooms};
+pub use requirements::{failed_requirement, level_meets_requirements, Requirement};
+
 mod requirements {
     use ndarray::Array2;
 
     use crate::cell::Cell;
     use crate::level::Level;
+    use crate::stats::RequirementRejections;
+
+    /// A requirement of the generated layouts, see [`level_meets_requirements`].
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    pub enum Requirement {
+        /// Enough floors for the boxes, the player and one free floor.
+        EnoughSpace,
+        /// All floors are connected.
+        Connectivity,
+        /// No floor has walls on three sides.
+        NoSurroundedFloors,
+        /// No space of 3x4 floors.
+        NoLargeSpaces,
+        /// Enough cells for the goals (see [`place_entities`](crate::place_entities)).
+        EnoughGoalPlaces,
+    }
 
-    /// Checks the generated level against the requirements and returns
-    /// false if the level should not be used.
-    pub(super) fn level_meets_requirements(level: &Level, box_count: u8) -> bool {
-        has_enough_space(level, box_count)
-            && has_connectivity(level)
-            && has_no_surrounded_floors(level)
-            && has_no_large_spaces(level)
-            && has_enough_goal_places(level, box_count)
+    impl Requirement {
+        /// Check the layout against this requirement.
+        pub fn is_met(self, level: &Level, box_count: u8) -> bool {
+            match self {
+                Self::EnoughSpace => has_enough_space(level, box_count),
+                Self::Connectivity => has_connectivity(level),
+                Self::NoSurroundedFloors => has_no_surrounded_floors(level),
+                Self::NoLargeSpaces => has_no_large_spaces(level),
+                Self::EnoughGoalPlaces => has_enough_goal_places(level, box_count),
+            }
+        }
+
+        /// Count the rejection of a layout by the requirement.
+        pub(super) fn count(self, rejections: &mut RequirementRejections) {
+            let counter = match self {
+                Self::EnoughSpace => &mut rejections.enough_space,
+                Self::Connectivity => &mut rejections.connectivity,
+                Self::NoSurroundedFloors => &mut rejections.no_surrounded_floors,
+                Self::NoLargeSpaces => &mut rejections.no_large_spaces,
+                Self::EnoughGoalPlaces => &mut rejections.enough_goal_places,
+            };
+            *counter += 1;
+        }
+    }
+
+    /// Checks a layout (e.g. of [`generate_rooms`](crate::generate_rooms)) against the
+    /// requirements and returns false if the layout should not be used.
+    /// This is the second stage of the generation.
+    pub fn level_meets_requirements(level: &Level, box_count: u8) -> bool {
+        failed_requirement(level, box_count).is_none()
+    }
+
+    /// Checks a layout against the requirements and returns the first
+    /// requirement that is not met, if the layout should not be used.
+    pub fn failed_requirement(level: &Level, box_count: u8) -> Option<Requirement> {
+        [
+            Requirement::EnoughSpace,
+            Requirement::Connectivity,
+            Requirement::NoSurroundedFloors,
+            Requirement::NoLargeSpaces,
+            Requirement::EnoughGoalPlaces,
+        ]
+        .into_iter()
+        .find(|requirement| !requirement.is_met(level, box_count))
     }
 
     /// Ensure that the level has enough space for the player, boxes, and one empty space.
@@ -223,25 +708,69 @@ mod requirements {
 
 mod generation {
     use ndarray::{Array2, s};
+    use rand::Rng;
 
     use crate::cell::Cell;
+    use crate::constraints::ConstraintMask;
     use crate::level::Level;
     use crate::room::get_random_room;
+    use crate::stats::GenerationStats;
+
+    /// Maximum number of drawn rooms for a slot with constraints, before the layout is abandoned.
+    const MAX_CONSTRAINED_DRAWS: u32 = 1000;
 
-    /// Generate a level with the given dimension of rooms (3x3 cells).
-    /// Each room is randomly chosen and rotated. Then the room is
+    /// Generate a layout of walls and floors with the given dimension of rooms
+    /// (3x3 cells), without the surrounding walls. This is the first stage of
+    /// the generation. Each room is randomly chosen and rotated. Then the room is
     /// checked against the surrounding rooms and placed if the room fits.
     ///
     /// The room fits if the outermost cells match the surroundings if they
     /// are not "EMPTY" cells.
-    pub(super) fn generate_rooms(height: usize, width: usize) -> Level {
+    pub fn generate_rooms(height: u8, width: u8, rng: &mut impl Rng) -> Level {
+        generate_rooms_with_stats(height.into(), width.into(), rng, None, &mut GenerationStats::default())
+            .expect("rooms without constraints always fit")
+    }
+
+    /// Generate a layout like [`generate_rooms`] for the size of the constraint
+    /// mask (see [`ConstraintMask::new`]). Preset rooms are placed first, the
+    /// other rooms are drawn until they fit the surroundings and the constraints.
+    /// Returns `None` if no drawn room fits a slot, another layout may work.
+    pub fn generate_constrained_rooms(constraints: &ConstraintMask, rng: &mut impl Rng) -> Option<Level> {
+        let (height, width) = constraints.dim();
+        generate_rooms_with_stats(height / 3, width / 3, rng, Some(constraints), &mut GenerationStats::default())
+    }
+
+    /// Generate a layout like [`generate_constrained_rooms`] and count the rooms that do not fit.
+    pub(super) fn generate_rooms_with_stats(
+        height: usize,
+        width: usize,
+        rng: &mut impl Rng,
+        constraints: Option<&ConstraintMask>,
+        stats: &mut GenerationStats,
+    ) -> Option<Level> {
         // Create empty level (w*3, h*3, because every room is 3x3)
         let mut level = Array2::from_elem((height * 3, width * 3), Cell::Empty);
         let mut filled_height = 0;
         let mut filled_width = 0;
 
+        // Place the preset rooms first, so that the surrounding rooms match them.
+        let mut preset = Array2::from_elem((height, width), false);
+        for ((room_y, room_x), is_preset) in preset.indexed_iter_mut() {
+            if let Some(room) = constraints.and_then(|constraints| constraints.preset_room(room_y, room_x)) {
+                level
+                    .slice_mut(s![room_y * 3..room_y * 3 + 3, room_x * 3..room_x * 3 + 3])
+                    .assign(&room);
+                *is_preset = true;
+            }
+        }
+
         // As long as there are empty rooms, fill them with random templates.
         while filled_width < width && filled_height < height {
+            if preset[[filled_height, filled_width]] {
+                next_room(&mut filled_height, &mut filled_width, width);
+                continue;
+            }
+
             let chunk_parts =
                 extract_surrounding_cells(&level, filled_height, filled_width, height, width);
 
@@ -250,8 +779,10 @@ mod generation {
                 filled_width * 3..filled_width * 3 + 3
             ]);
 
+            let mut draws = 0;
             loop {
-                let new_room = get_random_room();
+                let new_room = get_random_room(rng);
+                draws += 1;
 
                 let mut template_parts: Vec<Cell> = Vec::new();
                 if filled_width != 0 {
@@ -267,7 +798,13 @@ mod generation {
                     template_parts.extend(new_room.slice(s![1..=3, 4..=4]));
                 }
 
-                if !template_match(&chunk_parts, &template_parts) {
+                let fits_constraints = constraints
+                    .is_none_or(|constraints| constraints.allows_room(filled_height, filled_width, &new_room));
+                if !template_match(&chunk_parts, &template_parts) || !fits_constraints {
+                    stats.template_mismatches += 1;
+                    if constraints.is_some() && draws >= MAX_CONSTRAINED_DRAWS {
+                        return None;
+                    }
                     continue;
                 }
 
@@ -276,21 +813,25 @@ mod generation {
                 break;
             }
 
-            // update the filled indices.
-            if filled_width < width - 1 {
-                filled_width += 1;
-            } else {
-                filled_width = 0;
-                filled_height += 1;
-            }
+            next_room(&mut filled_height, &mut filled_width, width);
         }
 
-        level
+        Some(level)
+    }
+
+    /// Update the filled indices to the next room.
+    fn next_room(filled_height: &mut usize, filled_width: &mut usize, width: usize) {
+        if *filled_width < width - 1 {
+            *filled_width += 1;
+        } else {
+            *filled_width = 0;
+            *filled_height += 1;
+        }
     }
 
     /// Checks the chunk and the surrounding cells. If they match, the template
     /// (room) can be placed.
-    fn template_match(chunk_part: &Vec<Cell>, template_part: &Vec<Cell>) -> bool {
+    fn template_match(chunk_part: &[Cell], template_part: &[Cell]) -> bool {
         for (&left, &right) in chunk_part.iter().zip(template_part.iter()) {
             if left == Cell::Empty || right == Cell::Empty {
                 continue;
@@ -336,85 +877,136 @@ mod generation {
 }
 
 mod entities {
-    use std::collections::HashMap;
+    use std::collections::{HashMap, VecDeque};
 
     use ndarray::Array2;
     use rand::prelude::*;
 
     use crate::cell::Cell;
+    use crate::config::GenerationConfig;
+    use crate::constraints::ConstraintMask;
+    use crate::deadlock::pull_distances;
+    use crate::direction::Direction;
     use crate::level::Level;
+    use crate::scoring::StateCandidate;
+    use crate::stats::GenerationStats;
 
-    type TrackingState = (Vec<(usize, usize)>, (usize, usize), Level, i32);
+    type TrackingState = (Vec<(usize, usize)>, (usize, usize), Level, i32, PathMetrics);
     type StepMap = Array2<i32>;
-    type BacktrackMap = HashMap<String, (StepMap, Level)>;
-
-    #[derive(Clone, Copy, Debug, PartialEq)]
-    enum Direction {
-        Up,
-        Down,
-        Left,
-        Right,
+    type BacktrackMap = HashMap<String, (StepMap, Level, PathMetrics)>;
+
+    /// Number of states of the reverse search between two progress reports.
+    const SEARCH_REPORT_INTERVAL: usize = 1024;
+
+    /// Metrics of the path of the reverse search that reached a state.
+    #[derive(Clone, Debug)]
+    struct PathMetrics {
+        box_pushes: Vec<u32>,
+        moves: u32,
+        box_lines: u32,
+        last_pull: Option<(usize, Direction)>,
     }
 
-    impl Direction {
-        fn make_move(&self, x: usize, y: usize, width: usize, height: usize) -> Option<(usize, usize)> {
-            match self {
-                Self::Up if y > 0 => Some((x, y - 1)),
-                Self::Down if y < (height - 1) => Some((x, y + 1)),
-                Self::Left if x > 0 => Some((x - 1, y)),
-                Self::Right if x < (width - 1) => Some((x + 1, y)),
-                _ => None,
+    impl PathMetrics {
+        fn new(box_count: usize) -> Self {
+            Self {
+                box_pushes: vec![0; box_count],
+                moves: 0,
+                box_lines: 0,
+                last_pull: None,
             }
         }
 
-        fn iterator() -> impl Iterator<Item=Direction> {
-            [
-                Direction::Up,
-                Direction::Down,
-                Direction::Left,
-                Direction::Right,
-            ]
-                .iter()
-                .copied()
+        /// Add a pull of the box with the given index, after the player walked the given distance.
+        fn pull(&self, index: usize, direction: Direction, distance: u32) -> Self {
+            let mut metrics = self.clone();
+            metrics.box_pushes[index] += 1;
+            metrics.moves += distance + 1;
+            if metrics.last_pull != Some((index, direction)) {
+                metrics.box_lines += 1;
+            }
+            metrics.last_pull = Some((index, direction));
+            metrics
         }
     }
 
-    /// Places goals, boxes and the player in the level.
+    /// Places goals, boxes and the player in a layout that meets the requirements
+    /// (see [`level_meets_requirements`](crate::level_meets_requirements)).
+    /// This is the third stage of the generation, the result still needs the
+    /// surrounding walls of [`frame_level`](crate::frame_level).
+    /// Returns `None` if no start state is found, other goals may work.
+    ///
     /// To create an interesting level, the following steps are performed:
     /// 1. Calculate all possible goal locations and shuffle them
     /// 2. Select the first x goal locations (x = box count)
     /// 3. Place the boxes on the selected goal locations
     /// 4. For all player positions, calculate the backtrack map to see
     ///    the farthest states that boxes can be pushed to
-    /// 5. Return the farthest possible state
-    pub(super) fn place_entities(mut level: Level, box_count: usize) -> Option<Level> {
-        let goals = get_random_goal_locations(&level, box_count);
+    /// 5. Return the most interesting state according to the scorer of the
+    ///    configuration (by default, the farthest state)
+    ///
+    /// If the configuration requires a maximum number of pushes, farther
+    /// states are skipped. States that do not push every box often enough
+    /// or leave boxes on their goals are skipped if the configuration says so.
+    /// The pushes of a box are the minimum pushes to its nearest goal, goals
+    /// from which no box can be pulled that far are not selected.
+    pub fn place_entities(level: Level, config: &GenerationConfig, rng: &mut impl Rng) -> Option<Level> {
+        place_entities_with(level, config, rng, &|_| true, &mut GenerationStats::default())
+    }
+
+    /// Place the entities like [`place_entities`]. The reverse search calls
+    /// `search` regularly with the number of explored states and stops without
+    /// a level if it returns false. The size of the backtrack map is counted
+    /// in the statistics.
+    pub(super) fn place_entities_with(
+        mut level: Level,
+        config: &GenerationConfig,
+        rng: &mut impl Rng,
+        search: &dyn Fn(usize) -> bool,
+        stats: &mut GenerationStats,
+    ) -> Option<Level> {
+        let constraints = config.constraints.as_ref();
+        let goals = get_random_goal_locations(&level, config, rng, constraints);
+        let player_area = constraints.map(ConstraintMask::player_area).unwrap_or_default();
+        let max_pushes = config.pushes.as_ref().map(|range| *range.end() as i32);
+        let goal_distances = pull_distances(&level, goals.iter().copied());
 
         // h = height (aka y), w = width (aka x)
         for (h, w) in goals {
             level[[h, w]] = Cell::BoxOnGoal;
         }
 
-        let mut max_level = None;
-        let mut max_pos = None;
-        let mut max_steps = -1;
-
-        for (_, (steps, level)) in create_box_backtrack_map(&level) {
-            if let Some((index, &steps)) = steps.indexed_iter().max_by_key(|(_, &step)| step) {
-                if steps > max_steps {
-                    max_level = Some(level);
-                    max_pos = Some(index);
-                    max_steps = steps;
-                }
-            }
-        }
-
-        if max_level.is_none() || max_pos.is_none() {
-            return None;
-        }
+        let backtrack = create_box_backtrack_map(&level, search)?;
+        stats.backtrack_states += backtrack.len() as u64;
+        stats.max_backtrack_states = stats.max_backtrack_states.max(backtrack.len());
+        // sort the states, the order of the map would make seeded generations random.
+        let mut states = backtrack.iter().collect::<Vec<_>>();
+        states.sort_unstable_by_key(|(id, _)| *id);
+        let candidates = states
+            .into_iter()
+            .map(|(_, state)| state)
+            .filter(|(_, level, _)| meets_box_constraints(level, &goal_distances, config))
+            .filter_map(|(steps, level, metrics)| {
+                let (player, &pushes) = steps
+                    .indexed_iter()
+                    .filter(|(_, &step)| step > 0 && max_pushes.is_none_or(|max| step <= max))
+                    .filter(|(player, _)| player_area.is_empty() || player_area.contains(player))
+                    .max_by_key(|(_, &step)| step)?;
+
+                Some(StateCandidate {
+                    level,
+                    player,
+                    pushes: pushes as u32,
+                    moves: metrics.moves,
+                    box_lines: metrics.box_lines,
+                    box_pushes: &metrics.box_pushes,
+                })
+            })
+            .collect::<Vec<_>>();
 
-        let (player_y, player_x) = max_pos.unwrap();
-        let mut level = max_level.unwrap();
+        let candidate = candidates.get(config.scorer.select(&candidates, rng)?)?;
+        let (player_y, player_x) = candidate.player;
+        let mut level = candidate.level.clone();
         level[[player_y, player_x]] = match level[[player_y, player_x]] {
             Cell::Goal => Cell::PlayerOnGoal,
             _ => Cell::Player,
@@ -423,12 +1015,42 @@ mod entities {
         Some(level)
     }
 
+    /// Check the pushes of every box and the boxes on goals against the configuration.
+    /// The pushes of a box are the minimum pushes to the nearest goal (`goal_distances`),
+    /// so every solution pushes every box at least this often.
+    fn meets_box_constraints(level: &Level, goal_distances: &Array2<Option<u32>>, config: &GenerationConfig) -> bool {
+        if config.forbid_boxes_on_goals && level.iter().any(|&cell| cell == Cell::BoxOnGoal) {
+            return false;
+        }
+
+        level
+            .indexed_iter()
+            .filter(|(_, cell)| cell.is_box())
+            .all(|(position, _)| goal_distances[position].is_some_and(|pushes| pushes >= config.min_pushes_per_box))
+    }
+
     /// Calculate all possible goal locations, shuffle them and fetch the first
-    /// x locations.
-    fn get_random_goal_locations(level: &Level, box_count: usize) -> Vec<(usize, usize)> {
+    /// x locations (x = box count). The fixed goals of the constraints (on floors)
+    /// come first. Goals from which no box can be pulled as far as the minimum
+    /// pushes per box of the configuration are left out.
+    fn get_random_goal_locations(
+        level: &Level,
+        config: &GenerationConfig,
+        rng: &mut impl Rng,
+        constraints: Option<&ConstraintMask>,
+    ) -> Vec<(usize, usize)> {
+        let mut fixed_goals = constraints.map(ConstraintMask::goals).unwrap_or_default();
+        fixed_goals.retain(|&(y, x)| level.get((y, x)).is_some_and(Cell::is_floor));
+
         let mut possible_goals = get_possible_goal_locations(level);
-        possible_goals.shuffle(&mut thread_rng());
-        possible_goals.into_iter().take(box_count).collect()
+        possible_goals.retain(|goal| !fixed_goals.contains(goal));
+        if config.min_pushes_per_box > 0 {
+            possible_goals.retain(|&goal| {
+                pull_distances(level, [goal]).iter().flatten().any(|&pushes| pushes >= config.min_pushes_per_box)
+            });
+        }
+        possible_goals.shuffle(rng);
+        fixed_goals.into_iter().chain(possible_goals).take(config.boxes.into()).collect()
     }
 
     /// Get all possible goal locations by checking if a certain position has
@@ -471,7 +1093,8 @@ mod entities {
             .collect()
     }
 
-    fn create_box_backtrack_map(level: &Level) -> BacktrackMap {
+    /// Calls `search` every [`SEARCH_REPORT_INTERVAL`] states, the search is aborted if it returns false.
+    fn create_box_backtrack_map(level: &Level, search: &dyn Fn(usize) -> bool) -> Option<BacktrackMap> {
         let possible_player_positions = level
             .indexed_iter()
             .filter(|(_, &cell)| cell.is_floor())
@@ -486,36 +1109,42 @@ mod entities {
 
         let (height, width) = level.dim();
         let mut backtrack = HashMap::new();
+        let mut states_explored = 0;
         for (y, x) in possible_player_positions {
-            let mut stack = vec![(initial_boxes.clone(), (x, y).clone(), level.clone(), 0)];
+            let metrics = PathMetrics::new(initial_boxes.len());
+            let mut stack = vec![(initial_boxes.clone(), (x, y), level.clone(), 0, metrics)];
 
             while let Some(state) = stack.pop() {
+                states_explored += 1;
+                if states_explored % SEARCH_REPORT_INTERVAL == 0 && !search(states_explored) {
+                    return None;
+                }
+
                 if check_for_cached_map(&state, &mut backtrack) {
                     continue;
                 }
 
-                let (boxes, player, level, step) = state;
+                let (boxes, player, level, step, metrics) = state;
+                // one walk from the player serves all pulls of the state.
+                let distances = walk_distances(&level, player);
                 for (index, (box_y, box_x)) in boxes.iter().enumerate() {
                     for direction in Direction::iterator() {
                         // Move the box into the direction and see if it is still
                         // accessible.
                         let new_box_position = direction.make_move(*box_x, *box_y, width, height);
-                        if new_box_position.is_none()
-                            || !is_accessible(&level, player, new_box_position.unwrap())
-                        {
+                        let Some(distance) = new_box_position.and_then(|(x, y)| distances[[y, x]]) else {
                             continue;
-                        }
+                        };
                         let (box_new_x, box_new_y) = new_box_position.unwrap();
 
                         // Move the player to the same direction since the player
                         // must move the box in this direction.
                         let new_player_position = direction.make_move(box_new_x, box_new_y, width, height);
-                        if new_player_position.is_none()
-                            || !is_accessible(&level, player, new_player_position.unwrap())
-                        {
+                        let Some(new_player_position) =
+                            new_player_position.filter(|&(x, y)| distances[[y, x]].is_some())
+                        else {
                             continue;
-                        }
-                        let new_player_position = new_player_position.unwrap();
+                        };
 
                         let mut new_level = level.clone();
                         new_level[[*box_y, *box_x]] = match level[[*box_y, *box_x]] {
@@ -532,20 +1161,22 @@ mod entities {
                         let mut new_boxes = boxes.clone();
                         new_boxes[index] = (box_new_y, box_new_x);
 
-                        stack.push((new_boxes, new_player_position, new_level, step + 1));
+                        let new_metrics = metrics.pull(index, direction, distance);
+
+                        stack.push((new_boxes, new_player_position, new_level, step + 1, new_metrics));
                     }
                 }
             }
         }
 
-        backtrack
+        search(states_explored).then_some(backtrack)
     }
 
     /// Check for a cached map. If there exists a map, update the used steps
     /// if needed and return true. Otherwise, create a cached map with steps 0
     /// and return false.
     fn check_for_cached_map(state: &TrackingState, backtrack_map: &mut BacktrackMap) -> bool {
-        let (_, (player_x, player_y), level, step) = state;
+        let (_, (player_x, player_y), level, step, metrics) = state;
 
         if *step == 0 {
             return false;
@@ -553,7 +1184,7 @@ mod entities {
 
         let id = level_identifier(level);
 
-        if let Some((cached_map, _)) = backtrack_map.get_mut(&id) {
+        if let Some((cached_map, _, _)) = backtrack_map.get_mut(&id) {
             let used_steps = &cached_map[[*player_y, *player_x]];
             if *used_steps > 0 {
                 if *step < *used_steps {
@@ -563,10 +1194,13 @@ mod entities {
                 return true;
             }
         } else {
-            backtrack_map.insert(id.clone(), (Array2::from_elem(level.dim(), 0), level.clone()));
+            backtrack_map.insert(
+                id.clone(),
+                (Array2::from_elem(level.dim(), 0), level.clone(), metrics.clone()),
+            );
         }
 
-        let (map, _) = backtrack_map.get_mut(&id).unwrap();
+        let (map, _, _) = backtrack_map.get_mut(&id).unwrap();
         update_backtrack_steps(level, map, *player_x, *player_y, *step);
 
         false
@@ -605,10 +1239,38 @@ mod entities {
         level.iter().map(|&c| c.to_char()).collect()
     }
 
+    /// Calculate the number of moves of the shortest walks from the position to
+    /// all cells. Cells that are not accessible have no distance.
+    fn walk_distances(level: &Level, from: (usize, usize)) -> Array2<Option<u32>> {
+        let (height, width) = level.dim();
+        let (from_x, from_y) = from;
+        let mut distances = Array2::from_elem(level.dim(), None);
+
+        if from_x >= width || from_y >= height || !level[[from_y, from_x]].is_walkable() {
+            return distances;
+        }
+
+        let mut queue = VecDeque::from([(from_x, from_y, 0)]);
+        distances[[from_y, from_x]] = Some(0);
+
+        while let Some((x, y, distance)) = queue.pop_front() {
+            for direction in Direction::iterator() {
+                if let Some((next_x, next_y)) = direction.make_move(x, y, width, height) {
+                    if level[[next_y, next_x]].is_walkable() && distances[[next_y, next_x]].is_none() {
+                        distances[[next_y, next_x]] = Some(distance + 1);
+                        queue.push_back((next_x, next_y, distance + 1));
+                    }
+                }
+            }
+        }
+
+        distances
+    }
+
     /// Check if the given position is accessible from another position.
     /// The position is accessible, if there exists a way from x1,y1 to x2,y2
     /// which is walkable.
-    fn is_accessible(level: &Level, from: (usize, usize), to: (usize, usize)) -> bool {
+    pub(crate) fn is_accessible(level: &Level, from: (usize, usize), to: (usize, usize)) -> bool {
         let (height, width) = level.dim();
         let (from_x, from_y) = from;
         let (to_x, to_y) = to;
@@ -651,4 +1313,207 @@ mod entities {
 
         false
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::SolverLimits;
+
+    #[test]
+    fn parse_printed_level() {
+        let level = generate_level(2, 2, 2);
+
+        let result = parse_level(&pretty_print_level(&level)).unwrap();
+
+        assert_eq!(encode_level(&result), encode_level(&level));
+    }
+
+    #[test]
+    fn seeded_generation_is_reproducible() {
+        let mut config = GenerationConfig::new(3, 3, 2);
+        config.seed = Some(42);
+
+        assert_eq!(try_generate_level(&config), try_generate_level(&config));
+    }
+
+    #[test]
+    fn run_pipeline_stages() {
+        let config = GenerationConfig::new(2, 3, 1);
+        let mut rng = StdRng::seed_from_u64(5);
+
+        let layout = std::iter::repeat_with(|| generate_rooms(2, 3, &mut rng))
+            .find(|layout| level_meets_requirements(layout, 1))
+            .unwrap();
+        assert_eq!(layout.dim(), (6, 9));
+        assert_eq!(failed_requirement(&layout, 50), Some(Requirement::EnoughSpace));
+
+        let level = (0..10).find_map(|_| place_entities(layout.clone(), &config, &mut rng)).unwrap();
+        let level = frame_level(&level);
+        assert_eq!(level.dim(), (8, 11));
+        assert!(solve(&level, Metric::Pushes, &Default::default()).is_ok());
+    }
+
+    #[test]
+    fn place_entities_on_drawn_layout() {
+        let layout = parse_level("#######\n#     #\n#     #\n#  #  #\n#######").unwrap();
+        let mut config = GenerationConfig::new(1, 1, 2);
+        config.seed = Some(9);
+
+        let level = place_entities_on_layout(&layout, &config).unwrap();
+        assert_eq!(level.iter().filter(|cell| cell.is_box()).count(), 2);
+        assert_eq!(level.iter().filter(|cell| cell.is_player()).count(), 1);
+
+        config.boxes = 20;
+        assert!(matches!(
+            place_entities_on_layout(&layout, &config),
+            Err(LayoutError::NotEnoughGoalPlaces { boxes: 20, .. })
+        ));
+        assert!(matches!(
+            place_entities_on_layout(&level, &config),
+            Err(LayoutError::InvalidCell { .. })
+        ));
+    }
+
+    #[test]
+    fn parse_marks_outside_cells_as_empty() {
+        let result = parse_level("  ###\n### .#\n#  $@#\n######").unwrap();
+
+        assert_eq!(result[[0, 0]], Cell::Empty);
+        assert_eq!(result[[0, 5]], Cell::Empty);
+        assert_eq!(result[[1, 3]], Cell::Floor);
+        assert_eq!(result[[2, 1]], Cell::Floor);
+    }
+
+    #[test]
+    fn parse_invalid_character() {
+        let result = parse_level("####\n#@x#\n####");
+
+        assert_eq!(
+            result,
+            Err(ParseLevelError::InvalidCharacter { row: 1, column: 2, char: 'x' })
+        );
+    }
+
+    #[test]
+    fn generate_within_push_range() {
+        let mut config = GenerationConfig::new(2, 2, 1);
+        let pushes = |level: &Level| solve(level, Metric::Pushes, &SolverLimits::default()).unwrap().push_count();
+
+        for seed in 0..5 {
+            config.seed = Some(seed);
+            config.pushes = Some(5..=6);
+            assert!((5..=6).contains(&pushes(&try_generate_level(&config).unwrap())));
+
+            config.pushes = Some(0..=2);
+            assert!(pushes(&try_generate_level(&config).unwrap()) <= 2);
+        }
+    }
+
+    #[test]
+    fn generate_within_difficulty_band() {
+        let mut config = GenerationConfig::new(2, 2, 1);
+        config.difficulty = Some(10.0..=15.0);
+
+        for seed in 0..5 {
+            config.seed = Some(seed);
+            let stats = analyze_with_limits(&try_generate_level(&config).unwrap(), &SolverLimits::default());
+            let difficulty = stats.difficulty();
+            assert!(difficulty.is_some_and(|difficulty| (10.0..=15.0).contains(&difficulty)));
+        }
+    }
+
+    #[test]
+    fn exhaust_attempts() {
+        // 9 boxes never fit into a single room.
+        let mut config = GenerationConfig::new(1, 1, 9);
+        config.max_attempts = Some(5);
+        assert_eq!(try_generate_level(&config), Err(GenerationError::AttemptsExhausted { attempts: 5 }));
+
+        let mut config = GenerationConfig::new(2, 2, 1);
+        config.seed = Some(1);
+        config.max_attempts = Some(50);
+        config.pushes = Some(1000..=2000);
+        assert_eq!(try_generate_level(&config), Err(GenerationError::AttemptsExhausted { attempts: 50 }));
+    }
+
+    #[test]
+    fn every_box_needs_minimum_pushes() {
+        let mut config = GenerationConfig::new(3, 3, 2);
+        config.min_pushes_per_box = 4;
+        config.forbid_boxes_on_goals = true;
+
+        for seed in 0..20 {
+            config.seed = Some(seed);
+            let level = try_generate_level(&config).unwrap();
+            let solution = solve(&level, Metric::Pushes, &SolverLimits::default()).unwrap();
+
+            // follow the boxes through the solution and count their pushes.
+            let mut boxes = level
+                .indexed_iter()
+                .filter(|(_, cell)| cell.is_box())
+                .map(|(position, _)| (position, 0))
+                .collect::<Vec<_>>();
+            for push in &solution.pushes {
+                let (position, pushes) = boxes.iter_mut().find(|(position, _)| *position == push.from).unwrap();
+                *position = push.to();
+                *pushes += 1;
+            }
+
+            assert!(boxes.iter().all(|(_, pushes)| *pushes >= 4), "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn drop_layouts_without_start_state() {
+        // no box can be pushed that far, so every placement fails.
+        let mut config = GenerationConfig::new(2, 2, 1);
+        config.seed = Some(1);
+        config.max_attempts = Some(200);
+        config.min_pushes_per_box = 100;
+
+        let (result, stats) = try_generate_level_with_stats(&config, &GenerationControl::default());
+        let rejections = stats.rejections;
+        let accepted = stats.layouts
+            - rejections.enough_space
+            - rejections.connectivity
+            - rejections.no_surrounded_floors
+            - rejections.no_large_spaces
+            - rejections.enough_goal_places;
+
+        assert_eq!(result, Err(GenerationError::AttemptsExhausted { attempts: 200 }));
+        assert!(accepted > 1);
+        assert!(stats.placement_failures <= accepted * PLACEMENTS_PER_LAYOUT);
+    }
+
+    #[test]
+    fn decode_too_large_level() {
+        let wide = format!("{}#", MAX_LEVEL_SIZE);
+        let high = vec!["#"; MAX_LEVEL_SIZE].join("|");
+
+        assert_eq!(decode_level(&wide).unwrap().dim(), (1, MAX_LEVEL_SIZE));
+        assert_eq!(decode_level(&high).unwrap().dim(), (MAX_LEVEL_SIZE, 1));
+        assert_eq!(decode_level("999999999999#"), Err(ParseLevelError::TooLarge));
+        assert_eq!(decode_level("99999999999999999999999#"), Err(ParseLevelError::TooLarge));
+        assert_eq!(decode_level(&format!("{}#2-", MAX_LEVEL_SIZE - 1)), Err(ParseLevelError::TooLarge));
+        assert_eq!(decode_level(&format!("{}|#", high)), Err(ParseLevelError::TooLarge));
+        assert_eq!(parse_level(&"#".repeat(MAX_LEVEL_SIZE + 1)), Err(ParseLevelError::TooLarge));
+    }
+
+    #[test]
+    fn generation_stops_at_time_limit() {
+        let mut config = GenerationConfig::new(3, 3, 2);
+        config.max_duration = Some(std::time::Duration::ZERO);
+
+        assert_eq!(try_generate_level(&config), Err(GenerationError::TimeLimitReached { attempts: 0 }));
+
+        // a single reverse search of this configuration takes far longer than the limit.
+        let mut config = GenerationConfig::new(4, 4, 4);
+        config.seed = Some(3);
+        config.max_duration = Some(std::time::Duration::from_millis(200));
+        let start = Instant::now();
+
+        assert!(matches!(try_generate_level(&config), Err(GenerationError::TimeLimitReached { .. })));
+        assert!(start.elapsed() < std::time::Duration::from_secs(5));
+    }
+}
@@ -1,5 +1,60 @@
-pub use level::{encode_level, generate_level, pretty_print_level};
+pub use analysis::{analyze, analyze_with_limits, LevelStats, SolutionLength};
+pub use cell::Cell;
+pub use config::GenerationConfig;
+pub use constraints::{CellConstraint, ConstraintError, ConstraintMask};
+pub use control::{CancellationToken, GenerationControl, GenerationProgress, GenerationStage};
+pub use deadlock::{dead_squares, frozen_boxes, is_deadlocked, square_deadlocks};
+pub use direction::Direction;
+pub use game::{Game, GameError, SessionError};
+pub use hint::{hint, Hint};
+pub use level::{
+    decode_level, encode_level, failed_requirement, frame_level, generate_constrained_rooms,
+    generate_level, generate_rooms, level_meets_requirements, parse_level, place_entities,
+    place_entities_on_layout, pretty_print_level, try_generate_level, try_generate_level_with,
+    try_generate_level_with_stats, GenerationError, LayoutError, Level, ParseLevelError,
+    Requirement, MAX_LEVEL_SIZE,
+};
+pub use pack::{parse_pack, write_pack, PackEntry};
+pub use path::{box_path, player_path};
+pub use pool::{LevelPool, PoolStats};
+pub use puzzlescript::{
+    write_puzzlescript_legend, write_puzzlescript_level, write_puzzlescript_levels, PuzzleScriptLegend,
+};
+pub use raster::{render_png, render_png_with, render_ppm, render_ppm_with, Tileset, TilesetError, MAX_TILE_PX};
+pub use scoring::{
+    BoxLineScorer, MoveScorer, PushScorer, StateCandidate, StateScorer, TopKScorer, WeightedScorer,
+};
+pub use slc::{parse_slc, write_slc, SlcCollection, SlcError};
+pub use solver::{solve, Metric, Push, Solution, SolveError, SolverLimits};
+pub use stats::{GenerationStats, RequirementRejections};
+pub use svg::{render_svg, render_svg_replay, Palette, RenderOptions, Rgb};
+pub use terminal::{render_terminal, Overlay, TerminalOptions, Theme};
+#[cfg(feature = "tiled")]
+pub use tiled::{parse_tiled_map, write_tiled_map, TileIds, TiledError, TiledOptions};
 
+mod analysis;
 mod cell;
+mod config;
+mod constraints;
+mod control;
+mod deadlock;
+mod direction;
+mod game;
+mod hint;
 mod level;
+mod pack;
+mod path;
+mod pool;
+mod puzzlescript;
+mod raster;
 mod room;
+mod scoring;
+#[cfg(feature = "serde")]
+pub mod serde_level;
+mod slc;
+mod solver;
+mod stats;
+mod svg;
+mod terminal;
+#[cfg(feature = "tiled")]
+mod tiled;
@@ -0,0 +1,203 @@
+use crate::level::{level_from_rows, pretty_print_level, Level, ParseLevelError};
+
+/// A level of a pack file with its metadata.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PackEntry {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_level"))]
+    pub level: Level,
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub comments: Vec<String>,
+    /// Solutions in the LURD notation.
+    pub solutions: Vec<String>,
+}
+
+impl PackEntry {
+    /// Create an entry without metadata, e.g. for a generated level.
+    pub fn new(level: Level) -> Self {
+        Self {
+            level,
+            title: None,
+            author: None,
+            comments: Vec::new(),
+            solutions: Vec::new(),
+        }
+    }
+}
+
+/// Parse a pack file (XSB or SOK) with many levels. The metadata
+/// (`Title:`, `Author:`, `Solution:`, `Comment:` blocks and other text lines)
+/// follows the rows of a level. Text before the first level describes the
+/// pack itself and is skipped.
+///
+/// Fails if a level is too large (see [`MAX_LEVEL_SIZE`](crate::MAX_LEVEL_SIZE)).
+pub fn parse_pack(text: &str) -> Result<Vec<PackEntry>, ParseLevelError> {
+    let mut entries = Vec::new();
+    let mut rows: Vec<&str> = Vec::new();
+    let mut entry: Option<PackEntry> = None;
+    let mut in_comment = false;
+    let mut in_solution = false;
+
+    for line in text.lines() {
+        let line = line.trim_end();
+
+        if !in_comment && is_level_row(line) {
+            if rows.is_empty() {
+                entries.extend(entry.take());
+            }
+            rows.push(line);
+            in_solution = false;
+            continue;
+        }
+
+        if !rows.is_empty() {
+            entry = Some(PackEntry::new(level_from_rows(&rows)?));
+            rows.clear();
+        }
+
+        let Some(entry) = entry.as_mut() else {
+            continue;
+        };
+
+        let trimmed = line.trim();
+        let lowercase = trimmed.to_lowercase();
+
+        if in_comment {
+            if lowercase.starts_with("comment-end") || lowercase.starts_with("comment_end") {
+                in_comment = false;
+            } else {
+                entry.comments.push(trimmed.to_string());
+            }
+        } else if trimmed.is_empty() {
+            in_solution = false;
+        } else if let Some(title) = value_of(trimmed, "title") {
+            entry.title = Some(title.to_string());
+        } else if let Some(author) = value_of(trimmed, "author") {
+            entry.author = Some(author.to_string());
+        } else if lowercase.starts_with("solution") {
+            let solution = trimmed.split_once(':').map_or("", |(_, value)| value.trim());
+            entry.solutions.push(solution.to_string());
+            in_solution = true;
+        } else if in_solution && is_lurd(trimmed) {
+            entry.solutions.last_mut().expect("a solution was started").push_str(trimmed);
+        } else if let Some(comment) = value_of(trimmed, "comment") {
+            if comment.is_empty() {
+                in_comment = true;
+            } else {
+                entry.comments.push(comment.to_string());
+            }
+        } else {
+            entry.comments.push(trimmed.to_string());
+        }
+    }
+
+    if !rows.is_empty() {
+        entry = Some(PackEntry::new(level_from_rows(&rows)?));
+    }
+    entries.extend(entry);
+
+    Ok(entries)
+}
+
+/// Write the entries as a pack file in the SOK format. Every level is
+/// followed by its metadata and the entries are separated by blank lines.
+pub fn write_pack(entries: &[PackEntry]) -> String {
+    let mut pack = String::new();
+
+    for entry in entries {
+        for row in pretty_print_level(&entry.level).lines() {
+            pack.push_str(row.trim_end());
+            pack.push('\n');
+        }
+
+        if let Some(title) = &entry.title {
+            pack.push_str(&format!("Title: {}\n", title));
+        }
+        if let Some(author) = &entry.author {
+            pack.push_str(&format!("Author: {}\n", author));
+        }
+        if !entry.comments.is_empty() {
+            pack.push_str("Comment:\n");
+            for comment in &entry.comments {
+                pack.push_str(comment);
+                pack.push('\n');
+            }
+            pack.push_str("Comment-End:\n");
+        }
+        for solution in &entry.solutions {
+            pack.push_str(&format!("Solution: {}\n", solution));
+        }
+
+        pack.push('\n');
+    }
+
+    pack
+}
+
+/// A row of a level contains at least one wall and only level characters.
+fn is_level_row(line: &str) -> bool {
+    line.contains('#') && line.chars().all(|char| "#@+$*. -_".contains(char))
+}
+
+fn is_lurd(line: &str) -> bool {
+    line.chars().all(|char| "udlrUDLR".contains(char))
+}
+
+/// Returns the value of a `Key: value` line if the key matches (case insensitive).
+fn value_of<'a>(line: &'a str, key: &str) -> Option<&'a str> {
+    let (line_key, value) = line.split_once(':')?;
+    line_key.trim().eq_ignore_ascii_case(key).then(|| value.trim())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level::parse_level;
+
+    const PACK: &str = "My Pack
+Author: Someone
+
+    #####
+    #@$.#
+    #####
+Title: First
+Author: Jane Doe
+A nice level.
+Solution: R
+
+######
+#@ $.#
+######
+Title: Second
+Solution
+rR
+";
+
+    #[test]
+    fn parse_levels_with_metadata() {
+        let entries = parse_pack(PACK).unwrap();
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].title.as_deref(), Some("First"));
+        assert_eq!(entries[0].author.as_deref(), Some("Jane Doe"));
+        assert_eq!(entries[0].comments, vec!["A nice level."]);
+        assert_eq!(entries[0].solutions, vec!["R"]);
+        assert_eq!(entries[1].level, parse_level("######\n#@ $.#\n######").unwrap());
+        assert_eq!(entries[1].solutions, vec!["rR"]);
+    }
+
+    #[test]
+    fn write_and_parse_pack() {
+        let entries = parse_pack(PACK).unwrap();
+
+        assert_eq!(parse_pack(&write_pack(&entries)), Ok(entries));
+    }
+
+    #[test]
+    fn reject_too_large_levels() {
+        let pack = format!("{}\n#@$.#\nTitle: Wide\n", "#".repeat(1100));
+
+        assert_eq!(parse_pack(&pack), Err(ParseLevelError::TooLarge));
+    }
+}
@@ -0,0 +1,105 @@
+use std::collections::{HashSet, VecDeque};
+
+use crate::direction::Direction;
+use crate::level::Level;
+use crate::solver::Board;
+
+/// Find the shortest walk of the player to the position (y, x) without pushing a box.
+/// Returns `None` if the level has no single player or the position is not accessible.
+pub fn player_path(level: &Level, to: (usize, usize)) -> Option<Vec<Direction>> {
+    let (board, state) = Board::from_level(level).ok()?;
+    if !board.contains(to) {
+        return None;
+    }
+
+    board.walk(state.player, board.index(to), &board.box_mask(&state.boxes))
+}
+
+/// Find the moves (LURD notation) to push the box at the position (y, x) to the
+/// target (y, x), including the walks of the player between the pushes. The other
+/// boxes are not moved. The moves use the least pushes possible.
+/// Returns `None` if there is no box at the position or the target is not reachable.
+pub fn box_path(level: &Level, box_position: (usize, usize), target: (usize, usize)) -> Option<String> {
+    let (board, state) = Board::from_level(level).ok()?;
+    if !board.contains(box_position) || !board.contains(target) {
+        return None;
+    }
+
+    let from = board.index(box_position);
+    let to = board.index(target);
+    if !state.boxes.contains(&from) || board.is_wall(to) {
+        return None;
+    }
+
+    let mut others = board.box_mask(&state.boxes);
+    others[from] = false;
+
+    // nodes of the search: position of the box, position of the player,
+    // parent node and the push that led to the node.
+    let mut nodes = vec![(from, state.player, 0, None)];
+    let mut seen = HashSet::new();
+    let mut queue = VecDeque::from([0]);
+
+    while let Some(current) = queue.pop_front() {
+        let (position, player, _, _) = nodes[current];
+        if position == to {
+            let mut pushes = Vec::new();
+            let mut node = current;
+            while let (_, _, parent, Some(push)) = nodes[node] {
+                pushes.push(push);
+                node = parent;
+            }
+            pushes.reverse();
+
+            let steps = board.pushes_to_steps(&state, &pushes);
+            return Some(steps.iter().map(|(direction, push)| direction.to_char(*push)).collect());
+        }
+
+        let mut boxes = others.clone();
+        boxes[position] = true;
+        let reachable = board.reachable(player, &boxes);
+        let area = reachable.iter().position(|&reachable| reachable);
+        if !seen.insert((position, area)) {
+            continue;
+        }
+
+        for direction in Direction::iterator() {
+            let behind = board.neighbor(position, direction.opposite());
+            let next = board.neighbor(position, direction);
+            if let (Some(behind), Some(next)) = (behind, next) {
+                if reachable[behind] && !board.is_wall(next) && !others[next] {
+                    nodes.push((next, position, current, Some((position, direction))));
+                    queue.push_back(nodes.len() - 1);
+                }
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level::parse_level;
+
+    #[test]
+    fn player_path_around_box() {
+        let level = parse_level("#####\n#@$ #\n#   #\n#####").unwrap();
+
+        let path = player_path(&level, (1, 3)).unwrap();
+
+        assert_eq!(path, vec![Direction::Down, Direction::Right, Direction::Right, Direction::Up]);
+        assert_eq!(player_path(&level, (0, 0)), None);
+    }
+
+    #[test]
+    fn box_path_with_repositioning() {
+        let level = parse_level("######\n#    #\n#@$  #\n#    #\n#    #\n######").unwrap();
+
+        let moves = box_path(&level, (2, 2), (4, 3)).unwrap();
+
+        assert_eq!(moves.chars().filter(|char| char.is_ascii_uppercase()).count(), 3);
+        assert_eq!(box_path(&level, (1, 1), (3, 3)), None);
+    }
+}
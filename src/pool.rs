@@ -0,0 +1,375 @@
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::config::GenerationConfig;
+use crate::control::{CancellationToken, GenerationControl};
+use crate::level::{decode_level, encode_level, try_generate_level, try_generate_level_with, GenerationError, Level};
+
+/// Height, width and box count of a configuration.
+type PoolKey = (u8, u8, u8);
+
+/// Wait after the first failed generation of a configuration in the background.
+/// The wait doubles with every further failure, up to [`MAX_BACKOFF`].
+const BACKOFF: Duration = Duration::from_millis(100);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Maximum duration of the generation of a miss, if the configuration has no limit.
+const MISS_DURATION: Duration = Duration::from_secs(5);
+
+/// Statistics of a [`LevelPool`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PoolStats {
+    /// Requests that were served from the pool.
+    pub hits: u64,
+    /// Requests that found the pool empty and generated the level synchronously
+    /// (or returned no level because the configuration is backing off).
+    pub misses: u64,
+    /// Levels that were generated in the background.
+    pub generated: u64,
+    /// Generations in the background that failed (e.g. attempts exhausted).
+    pub failures: u64,
+    /// Levels that are available in the pool.
+    pub available: usize,
+}
+
+/// A pool of pre-generated levels. A background thread keeps `capacity`
+/// levels per configuration (height, width and box count) available,
+/// so that levels can be handed out without waiting for the generation.
+///
+/// The pool can be persisted to a file (one level per line), so that the
+/// levels survive restarts. The file is written when the pool is dropped.
+///
+/// The seeds of the configurations are ignored, a seed would fill the pool
+/// with copies of the same level. If the generation of a configuration fails
+/// (e.g. the attempts are exhausted), it is retried later and later.
+#[derive(Debug)]
+pub struct LevelPool {
+    shared: Arc<Shared>,
+    worker: Option<JoinHandle<()>>,
+}
+
+#[derive(Debug)]
+struct Shared {
+    configs: Vec<GenerationConfig>,
+    capacity: usize,
+    file: Option<PathBuf>,
+    state: Mutex<State>,
+    /// Stops the generation in the background when the pool is dropped.
+    cancellation: CancellationToken,
+    /// Notifies the worker when levels were taken or the pool shuts down.
+    refill: Condvar,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    levels: HashMap<PoolKey, VecDeque<Level>>,
+    stats: PoolStats,
+    /// Consecutive failures of the configurations and when to retry them.
+    backoff: HashMap<PoolKey, (u32, Instant)>,
+    shutdown: bool,
+}
+
+impl LevelPool {
+    /// Create a pool for the configurations and start filling it in the background.
+    /// Fails with [`io::ErrorKind::InvalidInput`] if two configurations have the
+    /// same height, width and box count, the pool could not tell them apart.
+    pub fn new(configs: Vec<GenerationConfig>, capacity: usize) -> io::Result<Self> {
+        check_keys(&configs)?;
+        Ok(Self::start(configs, capacity, None, State::default()))
+    }
+
+    /// Create a pool that is persisted to the file. Levels that were saved
+    /// to the file before are loaded into the pool. A missing file is created
+    /// when the pool is saved. The configurations are checked like in [`LevelPool::new`].
+    pub fn with_file(configs: Vec<GenerationConfig>, capacity: usize, path: impl AsRef<Path>) -> io::Result<Self> {
+        check_keys(&configs)?;
+        let path = path.as_ref().to_path_buf();
+        let mut state = State::default();
+
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(error) => return Err(error),
+        };
+        for line in content.lines() {
+            let Some((key, level)) = parse_line(line) else {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, format!("invalid pool entry '{}'", line)));
+            };
+            if configs.iter().any(|config| key_of(config) == key) {
+                let levels = state.levels.entry(key).or_default();
+                if levels.len() < capacity {
+                    levels.push_back(level);
+                }
+            }
+        }
+
+        Ok(Self::start(configs, capacity, Some(path), state))
+    }
+
+    fn start(configs: Vec<GenerationConfig>, capacity: usize, file: Option<PathBuf>, state: State) -> Self {
+        let configs = configs
+            .into_iter()
+            .map(|config| GenerationConfig { seed: None, ..config })
+            .collect();
+        let shared = Arc::new(Shared {
+            configs,
+            capacity,
+            file,
+            state: Mutex::new(state),
+            cancellation: CancellationToken::new(),
+            refill: Condvar::new(),
+        });
+
+        let worker = {
+            let shared = Arc::clone(&shared);
+            thread::spawn(move || shared.fill())
+        };
+
+        Self {
+            shared,
+            worker: Some(worker),
+        }
+    }
+
+    /// Take a level of the configuration from the pool. If the pool is empty,
+    /// the level is generated synchronously (a miss). The generation of a miss
+    /// stops after 5 seconds if the configuration has neither an attempt nor a
+    /// time limit. Returns `None` if the configuration is not part of the pool,
+    /// the generation fails or failed in the background and is not retried yet.
+    pub fn get(&self, height: u8, width: u8, boxes: u8) -> Option<Level> {
+        let key = (height, width, boxes);
+        let config = self.shared.configs.iter().find(|config| key_of(config) == key)?;
+
+        {
+            let mut state = self.shared.lock();
+            let level = state.levels.get_mut(&key).and_then(VecDeque::pop_front);
+            match level {
+                Some(_) => state.stats.hits += 1,
+                None => state.stats.misses += 1,
+            }
+            self.shared.refill.notify_one();
+
+            if level.is_some() {
+                return level;
+            }
+            if state.backoff.get(&key).is_some_and(|(_, retry)| *retry > Instant::now()) {
+                return None;
+            }
+        }
+
+        let config = match (config.max_attempts, config.max_duration) {
+            (None, None) => GenerationConfig {
+                max_duration: Some(MISS_DURATION),
+                ..config.clone()
+            },
+            _ => config.clone(),
+        };
+        try_generate_level(&config).ok()
+    }
+
+    /// The statistics of the pool since it was created.
+    pub fn stats(&self) -> PoolStats {
+        let state = self.shared.lock();
+
+        PoolStats {
+            available: state.levels.values().map(VecDeque::len).sum(),
+            ..state.stats
+        }
+    }
+
+    /// Write the levels of the pool to its file. Does nothing if the pool has no file.
+    pub fn save(&self) -> io::Result<()> {
+        let Some(path) = &self.shared.file else {
+            return Ok(());
+        };
+
+        let content = {
+            let state = self.shared.lock();
+            let mut keys = state.levels.keys().collect::<Vec<_>>();
+            keys.sort();
+
+            keys.into_iter()
+                .flat_map(|key| state.levels[key].iter().map(move |level| format_line(*key, level)))
+                .collect::<String>()
+        };
+
+        fs::write(path, content)
+    }
+}
+
+impl Drop for LevelPool {
+    fn drop(&mut self) {
+        self.shared.lock().shutdown = true;
+        self.shared.cancellation.cancel();
+        self.shared.refill.notify_one();
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+
+        let _ = self.save();
+    }
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|error| error.into_inner())
+    }
+
+    /// Generate levels for the configuration with the fewest levels,
+    /// until all configurations are full. Then wait for levels to be taken.
+    /// Configurations that failed are skipped until their backoff is over.
+    fn fill(&self) {
+        loop {
+            let config = {
+                let mut state = self.lock();
+                loop {
+                    if state.shutdown {
+                        return;
+                    }
+
+                    let now = Instant::now();
+                    let missing = self
+                        .configs
+                        .iter()
+                        .map(|config| (config, state.levels.get(&key_of(config)).map_or(0, VecDeque::len)))
+                        .filter(|(_, count)| *count < self.capacity)
+                        .map(|(config, count)| {
+                            let retry = state.backoff.get(&key_of(config)).map(|(_, retry)| *retry);
+                            (config, count, retry)
+                        })
+                        .collect::<Vec<_>>();
+                    let config = missing
+                        .iter()
+                        .filter(|(_, _, retry)| retry.is_none_or(|retry| retry <= now))
+                        .min_by_key(|(_, count, _)| *count)
+                        .map(|(config, _, _)| *config);
+                    let retry = missing.iter().filter_map(|(_, _, retry)| *retry).min();
+
+                    state = match (config, retry) {
+                        (Some(config), _) => break config,
+                        (None, Some(retry)) => {
+                            let timeout = retry.saturating_duration_since(now);
+                            self.refill.wait_timeout(state, timeout).unwrap_or_else(|error| error.into_inner()).0
+                        }
+                        (None, None) => self.refill.wait(state).unwrap_or_else(|error| error.into_inner()),
+                    };
+                }
+            };
+
+            let control = GenerationControl {
+                cancellation: Some(&self.cancellation),
+                progress: None,
+            };
+            let result = try_generate_level_with(config, &control);
+
+            let mut state = self.lock();
+            match result {
+                Ok(level) => {
+                    state.levels.entry(key_of(config)).or_default().push_back(level);
+                    state.backoff.remove(&key_of(config));
+                    state.stats.generated += 1;
+                }
+                Err(GenerationError::Cancelled { .. }) => return,
+                Err(_) => {
+                    let failures = state.backoff.get(&key_of(config)).map_or(0, |(failures, _)| *failures) + 1;
+                    let backoff = BACKOFF.saturating_mul(1 << (failures - 1).min(16)).min(MAX_BACKOFF);
+                    state.backoff.insert(key_of(config), (failures, Instant::now() + backoff));
+                    state.stats.failures += 1;
+                }
+            }
+        }
+    }
+}
+
+fn key_of(config: &GenerationConfig) -> PoolKey {
+    (config.height, config.width, config.boxes)
+}
+
+/// Reject configurations with the same key, they would share the levels.
+fn check_keys(configs: &[GenerationConfig]) -> io::Result<()> {
+    for (index, config) in configs.iter().enumerate() {
+        let key = key_of(config);
+        if configs[..index].iter().any(|other| key_of(other) == key) {
+            let message = format!("duplicate pool configuration {}x{} with {} boxes", key.0, key.1, key.2);
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, message));
+        }
+    }
+
+    Ok(())
+}
+
+/// A line of the pool file: `height width boxes encoded-level`.
+fn format_line((height, width, boxes): PoolKey, level: &Level) -> String {
+    format!("{} {} {} {}\n", height, width, boxes, encode_level(level))
+}
+
+fn parse_line(line: &str) -> Option<(PoolKey, Level)> {
+    let mut parts = line.split_whitespace();
+    let height = parts.next()?.parse().ok()?;
+    let width = parts.next()?.parse().ok()?;
+    let boxes = parts.next()?.parse().ok()?;
+    let level = decode_level(parts.next()?).ok()?;
+
+    Some(((height, width, boxes), level))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wait_until_full(pool: &LevelPool, available: usize) {
+        let start = Instant::now();
+        while pool.stats().available < available {
+            assert!(start.elapsed() < Duration::from_secs(10), "the pool was not filled");
+            thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    #[test]
+    fn serve_and_persist_levels() {
+        let path = std::env::temp_dir().join(format!("sokoban-pool-{}.txt", std::process::id()));
+        let configs = vec![GenerationConfig::new(2, 2, 1)];
+
+        let pool = LevelPool::with_file(configs.clone(), 2, &path).unwrap();
+        wait_until_full(&pool, 2);
+        assert!(pool.get(2, 2, 1).is_some());
+        assert!(pool.get(3, 3, 1).is_none());
+        let duplicate = LevelPool::with_file(vec![configs[0].clone(), configs[0].clone()], 2, &path);
+        assert_eq!(duplicate.unwrap_err().kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(pool.stats().hits, 1);
+        wait_until_full(&pool, 2);
+        drop(pool);
+
+        let pool = LevelPool::with_file(configs, 2, &path).unwrap();
+        assert_eq!(pool.stats().available, 2);
+        assert_eq!(pool.stats().generated, 0);
+
+        drop(pool);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn back_off_after_failures() {
+        // 9 boxes never fit into a single room.
+        let mut failing = GenerationConfig::new(1, 1, 9);
+        failing.max_attempts = Some(5);
+        let mut seeded = GenerationConfig::new(2, 2, 1);
+        seeded.seed = Some(7);
+
+        let pool = LevelPool::new(vec![failing, seeded], 1).unwrap();
+        wait_until_full(&pool, 1);
+        thread::sleep(Duration::from_millis(250));
+
+        assert!((1..=3).contains(&pool.stats().failures));
+        assert!(pool.shared.configs.iter().all(|config| config.seed.is_none()));
+        // the failing configuration is not generated while it backs off.
+        assert!(pool.get(1, 1, 9).is_none());
+        assert_eq!(pool.stats().misses, 1);
+    }
+}
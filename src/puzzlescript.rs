@@ -0,0 +1,126 @@
+use crate::cell::Cell;
+use crate::level::Level;
+use crate::pack::PackEntry;
+
+/// Characters of the cells in a [PuzzleScript](https://www.puzzlescript.net) level.
+/// The objects are named like in the Sokoban example of PuzzleScript
+/// (`Background`, `Wall`, `Player`, `Crate` and `Target`). PuzzleScript ignores
+/// the case of the characters, so they must differ in more than their case.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PuzzleScriptLegend {
+    /// Cells outside of the walls.
+    pub empty: char,
+    pub floor: char,
+    pub wall: char,
+    pub goal: char,
+    pub player: char,
+    pub player_on_goal: char,
+    pub crate_on_floor: char,
+    pub crate_on_goal: char,
+}
+
+impl PuzzleScriptLegend {
+    fn to_char(self, cell: Cell) -> char {
+        match cell {
+            Cell::Empty => self.empty,
+            Cell::Floor | Cell::SpecialFloor => self.floor,
+            Cell::Wall => self.wall,
+            Cell::Goal => self.goal,
+            Cell::Player => self.player,
+            Cell::PlayerOnGoal => self.player_on_goal,
+            Cell::Box => self.crate_on_floor,
+            Cell::BoxOnGoal => self.crate_on_goal,
+        }
+    }
+}
+
+/// The legend of the Sokoban example of PuzzleScript, with `Q` for the player on a target.
+impl Default for PuzzleScriptLegend {
+    fn default() -> Self {
+        Self {
+            empty: '.',
+            floor: '.',
+            wall: '#',
+            goal: 'O',
+            player: 'P',
+            player_on_goal: 'Q',
+            crate_on_floor: '*',
+            crate_on_goal: '@',
+        }
+    }
+}
+
+/// Write the `LEGEND` section with the characters of the legend.
+pub fn write_puzzlescript_legend(legend: &PuzzleScriptLegend) -> String {
+    let mut section = String::from("========\nLEGEND\n========\n\n");
+
+    let mut definitions = vec![(legend.floor, "Background")];
+    if legend.empty != legend.floor {
+        definitions.push((legend.empty, "Background"));
+    }
+    definitions.extend([
+        (legend.wall, "Wall"),
+        (legend.player, "Player"),
+        (legend.crate_on_floor, "Crate"),
+        (legend.crate_on_goal, "Crate and Target"),
+        (legend.goal, "Target"),
+        (legend.player_on_goal, "Player and Target"),
+    ]);
+
+    for (char, objects) in definitions {
+        section.push_str(&format!("{} = {}\n", char, objects));
+    }
+
+    section
+}
+
+/// Write the rows of the level with the characters of the legend.
+pub fn write_puzzlescript_level(level: &Level, legend: &PuzzleScriptLegend) -> String {
+    let mut rows = String::with_capacity(level.len() + level.nrows());
+
+    for row in level.rows() {
+        rows.extend(row.iter().map(|cell| legend.to_char(*cell)));
+        rows.push('\n');
+    }
+
+    rows
+}
+
+/// Write the `LEVELS` section with all levels of the pack. The title
+/// of an entry is written as a `message` line before its level.
+pub fn write_puzzlescript_levels(entries: &[PackEntry], legend: &PuzzleScriptLegend) -> String {
+    let mut section = String::from("=======\nLEVELS\n=======\n\n");
+
+    for entry in entries {
+        if let Some(title) = &entry.title {
+            section.push_str(&format!("message {}\n\n", title));
+        }
+        section.push_str(&write_puzzlescript_level(&entry.level, legend));
+        section.push('\n');
+    }
+
+    section
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level::parse_level;
+
+    #[test]
+    fn write_levels_with_messages() {
+        let mut entry = PackEntry::new(parse_level("  #####\n###@$.#\n#######").unwrap());
+        entry.title = Some("First".to_string());
+        let legend = PuzzleScriptLegend {
+            empty: '-',
+            ..Default::default()
+        };
+
+        assert_eq!(
+            write_puzzlescript_levels(&[entry], &legend),
+            "=======\nLEVELS\n=======\n\nmessage First\n\n--#####\n###P*O#\n#######\n\n"
+        );
+        assert!(write_puzzlescript_legend(&legend).contains("\n- = Background\n# = Wall\n"));
+    }
+}
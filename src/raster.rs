@@ -0,0 +1,402 @@
+use std::error::Error;
+use std::fmt;
+
+use crate::cell::Cell;
+use crate::level::Level;
+use crate::svg::{Palette, Rgb};
+
+/// Number of tiles in a tileset.
+const TILES: usize = 7;
+
+/// Maximum size of a tile in pixels of the rendered images,
+/// larger sizes are reduced to it.
+pub const MAX_TILE_PX: u32 = 256;
+
+/// Tiles of the cells for raster images. A tileset is a strip of 7 square tiles
+/// in the order wall, floor, goal, box on floor, box on goal, player on floor
+/// and player on goal. Empty cells outside of the walls are transparent.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Tileset {
+    tile_size: u32,
+    /// RGBA pixels of the strip, row by row.
+    pixels: Vec<u8>,
+}
+
+/// Error that occurs when a tileset image cannot be used.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TilesetError {
+    /// The image is not a binary PPM (P6) image with 8 bits per channel.
+    InvalidPpm,
+    /// The image is not a strip of 7 square tiles, or the pixels do not match the size.
+    InvalidSize { width: u32, height: u32 },
+}
+
+impl fmt::Display for TilesetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidPpm => write!(f, "the tileset is not a binary PPM image"),
+            Self::InvalidSize { width, height } => write!(
+                f,
+                "a tileset of {}x{} pixels is not a strip of {} square tiles",
+                width, height, TILES
+            ),
+        }
+    }
+}
+
+impl Error for TilesetError {}
+
+impl Tileset {
+    /// Create a tileset from the RGBA pixels of a strip with 7 tiles
+    /// (`7 * tile_size` pixels wide and `tile_size` pixels high).
+    pub fn new(tile_size: u32, pixels: Vec<u8>) -> Result<Self, TilesetError> {
+        let width = (TILES as u32).checked_mul(tile_size);
+        if tile_size == 0 || width.and_then(|width| image_size(width, tile_size, 4)) != Some(pixels.len()) {
+            return Err(TilesetError::InvalidSize {
+                width: width.unwrap_or(u32::MAX),
+                height: tile_size,
+            });
+        }
+
+        Ok(Self { tile_size, pixels })
+    }
+
+    /// Read the tileset from a binary PPM (P6) image with a strip of 7 tiles.
+    pub fn from_ppm(data: &[u8]) -> Result<Self, TilesetError> {
+        // the header consists of the magic number, width, height and maximum
+        // value, separated by whitespace and followed by a single whitespace.
+        let mut fields = Vec::new();
+        let mut position = 0;
+        while fields.len() < 4 {
+            while data.get(position).is_some_and(|byte| byte.is_ascii_whitespace()) {
+                position += 1;
+            }
+            if data.get(position) == Some(&b'#') {
+                while data.get(position).is_some_and(|byte| *byte != b'\n') {
+                    position += 1;
+                }
+                continue;
+            }
+
+            let start = position;
+            while data.get(position).is_some_and(|byte| !byte.is_ascii_whitespace()) {
+                position += 1;
+            }
+            if start == position {
+                return Err(TilesetError::InvalidPpm);
+            }
+            fields.push(std::str::from_utf8(&data[start..position]).map_err(|_| TilesetError::InvalidPpm)?);
+        }
+
+        let [magic, width, height, max] = fields[..] else {
+            return Err(TilesetError::InvalidPpm);
+        };
+        let (Ok(width), Ok(height)) = (width.parse::<u32>(), height.parse::<u32>()) else {
+            return Err(TilesetError::InvalidPpm);
+        };
+        if magic != "P6" || max != "255" {
+            return Err(TilesetError::InvalidPpm);
+        }
+
+        let rgb = &data[(position + 1).min(data.len())..];
+        let size = image_size(width, height, 3);
+        if (TILES as u32).checked_mul(height) != Some(width) || size.is_none_or(|size| rgb.len() < size) {
+            return Err(TilesetError::InvalidSize { width, height });
+        }
+
+        let pixels = rgb
+            .chunks_exact(3)
+            .take(width as usize * height as usize)
+            .flat_map(|pixel| [pixel[0], pixel[1], pixel[2], 255])
+            .collect();
+
+        Self::new(height, pixels)
+    }
+
+    /// Tiles with one flat colour per cell, from the palette.
+    pub fn flat(palette: &Palette) -> Self {
+        let colors = [
+            palette.wall,
+            palette.floor,
+            palette.goal,
+            palette.box_on_floor,
+            palette.box_on_goal,
+            palette.player,
+            palette.player,
+        ];
+
+        Self {
+            tile_size: 1,
+            pixels: colors.iter().flat_map(|color| [color.0, color.1, color.2, 255]).collect(),
+        }
+    }
+
+    /// The built-in tileset, drawn with the colours of the palette. The
+    /// tile size is at most [`MAX_TILE_PX`].
+    pub fn from_palette(palette: &Palette, tile_size: u32) -> Self {
+        let size = tile_size.clamp(1, MAX_TILE_PX);
+        let mut tileset = Self {
+            tile_size: size,
+            pixels: vec![0; TILES * size as usize * size as usize * 4],
+        };
+
+        for tile in 0..TILES {
+            let ground = if tile == 0 { palette.wall } else { palette.floor };
+            let goal = matches!(tile, 2 | 4 | 6);
+
+            tileset.draw(tile, |_, _| Some(ground));
+            if goal {
+                tileset.draw(tile, |x, y| (x * x + y * y <= 0.04).then_some(palette.goal));
+            }
+            match tile {
+                3 | 4 => {
+                    let color = if goal { palette.box_on_goal } else { palette.box_on_floor };
+                    tileset.draw(tile, |x, y| (x.abs() <= 0.4 && y.abs() <= 0.4).then_some(color));
+                }
+                5 | 6 => tileset.draw(tile, |x, y| (x * x + y * y <= 0.1225).then_some(palette.player)),
+                _ => {}
+            }
+        }
+
+        tileset
+    }
+
+    /// Draw on the tile. The shape gets the position relative to the
+    /// center of the tile (from -0.5 to 0.5) and returns the colour.
+    fn draw(&mut self, tile: usize, shape: impl Fn(f64, f64) -> Option<Rgb>) {
+        let size = self.tile_size as usize;
+        let width = TILES * size;
+
+        for y in 0..size {
+            for x in 0..size {
+                let relative = |value: usize| (value as f64 + 0.5) / size as f64 - 0.5;
+                if let Some(color) = shape(relative(x), relative(y)) {
+                    let index = (y * width + tile * size + x) * 4;
+                    self.pixels[index..index + 4].copy_from_slice(&[color.0, color.1, color.2, 255]);
+                }
+            }
+        }
+    }
+
+    /// The index of the tile of the cell, `None` for transparent cells.
+    fn tile(cell: Cell) -> Option<usize> {
+        match cell {
+            Cell::Empty => None,
+            Cell::Wall => Some(0),
+            Cell::Floor | Cell::SpecialFloor => Some(1),
+            Cell::Goal => Some(2),
+            Cell::Box => Some(3),
+            Cell::BoxOnGoal => Some(4),
+            Cell::Player => Some(5),
+            Cell::PlayerOnGoal => Some(6),
+        }
+    }
+
+    /// The RGBA pixel of the tile, scaled to tiles of `tile_px` pixels.
+    fn pixel(&self, tile: usize, x: usize, y: usize, tile_px: usize) -> &[u8] {
+        let size = self.tile_size as usize;
+        let x = tile * size + x * size / tile_px;
+        let y = y * size / tile_px;
+        let index = (y * TILES * size + x) * 4;
+
+        &self.pixels[index..index + 4]
+    }
+}
+
+impl Default for Tileset {
+    fn default() -> Self {
+        Self::from_palette(&Palette::default(), 16)
+    }
+}
+
+/// Render the level as a PNG image with the built-in tileset in the given
+/// size (at most [`MAX_TILE_PX`]). Empty cells outside of the walls are transparent.
+pub fn render_png(level: &Level, tile_px: u32) -> Vec<u8> {
+    render_png_with(level, tile_px, &Tileset::from_palette(&Palette::default(), tile_px))
+}
+
+/// Render the level as a PNG image with the tiles of the tileset, scaled to `tile_px`
+/// (at most [`MAX_TILE_PX`]).
+pub fn render_png_with(level: &Level, tile_px: u32, tileset: &Tileset) -> Vec<u8> {
+    let (width, height, pixels) = rasterize(level, tile_px, tileset);
+
+    // every row starts with the filter type (0, no filter).
+    let row_length = width * 4;
+    let mut data = Vec::with_capacity((row_length + 1) * height);
+    for row in pixels.chunks_exact(row_length.max(1)).take(height) {
+        data.push(0);
+        data.extend_from_slice(row);
+    }
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&(width as u32).to_be_bytes());
+    header.extend_from_slice(&(height as u32).to_be_bytes());
+    // 8 bits per channel, RGBA, default compression, filter and no interlacing.
+    header.extend_from_slice(&[8, 6, 0, 0, 0]);
+
+    let mut png = vec![0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+    png_chunk(&mut png, b"IHDR", &header);
+    png_chunk(&mut png, b"IDAT", &zlib_stored(&data));
+    png_chunk(&mut png, b"IEND", &[]);
+
+    png
+}
+
+/// Render the level as a binary PPM (P6) image with the built-in tileset in the given
+/// size (at most [`MAX_TILE_PX`]). PPM has no transparency, empty cells are white.
+pub fn render_ppm(level: &Level, tile_px: u32) -> Vec<u8> {
+    render_ppm_with(level, tile_px, &Tileset::from_palette(&Palette::default(), tile_px))
+}
+
+/// Render the level as a binary PPM (P6) image with the tiles of the tileset, scaled to
+/// `tile_px` (at most [`MAX_TILE_PX`]).
+pub fn render_ppm_with(level: &Level, tile_px: u32, tileset: &Tileset) -> Vec<u8> {
+    let (width, height, pixels) = rasterize(level, tile_px, tileset);
+
+    let mut ppm = format!("P6\n{} {}\n255\n", width, height).into_bytes();
+    for pixel in pixels.chunks_exact(4) {
+        // blend with a white background.
+        let alpha = pixel[3] as u32;
+        ppm.extend(pixel[..3].iter().map(|channel| ((*channel as u32 * alpha + 255 * (255 - alpha)) / 255) as u8));
+    }
+
+    ppm
+}
+
+/// Draw the level into RGBA pixels. Returns the width, height and pixels.
+fn rasterize(level: &Level, tile_px: u32, tileset: &Tileset) -> (usize, usize, Vec<u8>) {
+    let tile_px = tile_px.min(MAX_TILE_PX) as usize;
+    let (rows, columns) = level.dim();
+    let width = columns * tile_px;
+    let height = rows * tile_px;
+    let mut pixels = vec![0; width * height * 4];
+
+    for ((row, column), cell) in level.indexed_iter() {
+        let Some(tile) = Tileset::tile(*cell) else {
+            continue;
+        };
+
+        for y in 0..tile_px {
+            for x in 0..tile_px {
+                let index = ((row * tile_px + y) * width + column * tile_px + x) * 4;
+                pixels[index..index + 4].copy_from_slice(tileset.pixel(tile, x, y, tile_px));
+            }
+        }
+    }
+
+    (width, height, pixels)
+}
+
+/// The number of bytes of an image, `None` if the size overflows.
+fn image_size(width: u32, height: u32, channels: usize) -> Option<usize> {
+    usize::try_from(width)
+        .ok()?
+        .checked_mul(usize::try_from(height).ok()?)?
+        .checked_mul(channels)
+}
+
+fn png_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc = crc32(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// Wrap the data in a zlib stream with uncompressed (stored) deflate blocks.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut stream = vec![0x78, 0x01];
+
+    let mut blocks = data.chunks(u16::MAX as usize).peekable();
+    if blocks.peek().is_none() {
+        stream.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        let length = block.len() as u16;
+        stream.push(blocks.peek().is_none() as u8);
+        stream.extend_from_slice(&length.to_le_bytes());
+        stream.extend_from_slice(&(!length).to_le_bytes());
+        stream.extend_from_slice(block);
+    }
+
+    stream.extend_from_slice(&adler32(data).to_be_bytes());
+    stream
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0u32, |crc, byte| {
+        (0..8).fold(crc ^ *byte as u32, |crc, _| {
+            if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            }
+        })
+    })
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (a, b) = data.iter().fold((1u32, 0u32), |(a, b), byte| {
+        let a = (a + *byte as u32) % 65521;
+        (a, (b + a) % 65521)
+    });
+
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level::parse_level;
+
+    #[test]
+    fn checksums() {
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+    }
+
+    #[test]
+    fn render_flat_ppm() {
+        let level = parse_level(" ###\n #@#\n ###").unwrap();
+        let palette = Palette::default();
+
+        let ppm = render_ppm_with(&level, 2, &Tileset::flat(&palette));
+        let header = b"P6\n8 6\n255\n";
+
+        assert_eq!(&ppm[..header.len()], header);
+        assert_eq!(ppm.len(), header.len() + 8 * 6 * 3);
+        // the first pixel is empty and the player is at row 2, column 4.
+        assert_eq!(&ppm[header.len()..header.len() + 3], &[255, 255, 255]);
+        let player = header.len() + (2 * 8 + 4) * 3;
+        assert_eq!(&ppm[player..player + 3], &[palette.player.0, palette.player.1, palette.player.2]);
+
+        let tileset = Tileset::from_ppm(&render_ppm_with(&parse_level("#@").unwrap(), 1, &Tileset::flat(&palette)));
+        assert_eq!(tileset, Err(TilesetError::InvalidSize { width: 2, height: 1 }));
+    }
+
+    #[test]
+    fn reject_huge_tilesets() {
+        let tileset = Tileset::from_ppm(b"P6\n700000 100000\n255\n\0\0\0");
+        assert_eq!(tileset, Err(TilesetError::InvalidSize { width: 700_000, height: 100_000 }));
+
+        let tileset = Tileset::from_ppm(format!("P6\n{} {}\n255\n", u32::MAX - 6, u32::MAX / 7).as_bytes());
+        assert_eq!(tileset, Err(TilesetError::InvalidSize { width: u32::MAX - 6, height: u32::MAX / 7 }));
+
+        let tileset = Tileset::new(u32::MAX, Vec::new());
+        assert_eq!(tileset, Err(TilesetError::InvalidSize { width: u32::MAX, height: u32::MAX }));
+    }
+
+    #[test]
+    fn render_png_image() {
+        let png = render_png(&parse_level("#####\n#@$.#\n#####").unwrap(), 8);
+
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+        assert_eq!(&png[16..24], &[0, 0, 0, 40, 0, 0, 0, 24]);
+        assert!(png.ends_with(b"IEND\xae\x42\x60\x82"));
+
+        // larger tiles are reduced to the maximum size.
+        let png = render_png(&parse_level("#@").unwrap(), 9000);
+        assert_eq!(&png[16..24], &[0, 0, 2, 0, 0, 0, 1, 0]);
+    }
+}
@@ -11,8 +11,7 @@ type Template = [[Cell; 5]; 5];
 pub(crate) type Room = Array2<Cell>;
 
 /// Returns a randomly rotated template as a room.
-pub(crate) fn get_random_room() -> Room {
-    let mut rng = rand::thread_rng();
+pub(crate) fn get_random_room(rng: &mut impl Rng) -> Room {
     let index = rng.gen_range(0..TEMPLATES.len());
     let mut template = TEMPLATES[index];
 
@@ -26,6 +25,15 @@ pub(crate) fn get_random_room() -> Room {
     Room::from(template.to_vec())
 }
 
+/// Returns all templates in all rotations as rooms.
+pub(crate) fn all_rooms() -> impl Iterator<Item = Room> {
+    TEMPLATES.iter().flat_map(|&template| {
+        std::iter::successors(Some(template), |&template| Some(rotate_template(template)))
+            .take(4)
+            .map(|template| Room::from(template.to_vec()))
+    })
+}
+
 fn rotate_template(template: Template) -> Template {
     let mut new_template = [[Cell::Empty; 5]; 5];
 
@@ -38,8 +46,8 @@ fn rotate_template(template: Template) -> Template {
     }
 
     // reverse the rows
-    for y in 0..5 {
-        new_template[y].reverse();
+    for row in new_template.iter_mut() {
+        row.reverse();
     }
 
     new_template
@@ -0,0 +1,176 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::sync::Arc;
+
+use rand::prelude::*;
+
+use crate::level::Level;
+
+/// A possible start state of a level, found by the reverse search from the goals.
+/// The numbers are counted along the path of the reverse search and are not
+/// necessarily optimal.
+#[derive(Clone, Copy, Debug)]
+pub struct StateCandidate<'a> {
+    /// The level with the boxes on their start positions, without the player.
+    pub level: &'a Level,
+    /// Start position (y, x) of the player.
+    pub player: (usize, usize),
+    pub pushes: u32,
+    pub moves: u32,
+    /// Number of box lines (pushes of the same box in the same direction).
+    pub box_lines: u32,
+    /// Number of pushes of every box.
+    pub box_pushes: &'a [u32],
+}
+
+/// Decides which start state is the most interesting one. Used by the
+/// generator to choose the start state among all states of the reverse search.
+pub trait StateScorer: fmt::Debug + Send + Sync {
+    /// Score the state, higher is more interesting.
+    fn score(&self, candidate: &StateCandidate) -> f64;
+
+    /// Select the index of the most interesting candidate. Randomness must come
+    /// from `rng`, so that seeded generations are reproducible.
+    /// Defaults to the candidate with the highest score.
+    fn select(&self, candidates: &[StateCandidate], _rng: &mut dyn RngCore) -> Option<usize> {
+        candidates
+            .iter()
+            .map(|candidate| self.score(candidate))
+            .enumerate()
+            .max_by(|(_, left), (_, right)| left.partial_cmp(right).unwrap_or(Ordering::Equal))
+            .map(|(index, _)| index)
+    }
+}
+
+/// Scores the states by the number of pushes (the "farthest" state).
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PushScorer;
+
+impl StateScorer for PushScorer {
+    fn score(&self, candidate: &StateCandidate) -> f64 {
+        candidate.pushes as f64
+    }
+}
+
+/// Scores the states by the number of player moves.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MoveScorer;
+
+impl StateScorer for MoveScorer {
+    fn score(&self, candidate: &StateCandidate) -> f64 {
+        candidate.moves as f64
+    }
+}
+
+/// Scores the states by the number of box lines, i.e. how often
+/// the player changes the box or the direction of the pushes.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BoxLineScorer;
+
+impl StateScorer for BoxLineScorer {
+    fn score(&self, candidate: &StateCandidate) -> f64 {
+        candidate.box_lines as f64
+    }
+}
+
+/// Scores the states by the weighted sum of other scorers.
+#[derive(Clone, Debug, Default)]
+pub struct WeightedScorer {
+    pub scorers: Vec<(f64, Arc<dyn StateScorer>)>,
+}
+
+impl StateScorer for WeightedScorer {
+    fn score(&self, candidate: &StateCandidate) -> f64 {
+        self.scorers
+            .iter()
+            .map(|(weight, scorer)| weight * scorer.score(candidate))
+            .sum()
+    }
+}
+
+/// Selects a random state among the `k` states with the highest score.
+#[derive(Clone, Debug)]
+pub struct TopKScorer {
+    pub scorer: Arc<dyn StateScorer>,
+    pub k: usize,
+}
+
+impl StateScorer for TopKScorer {
+    fn score(&self, candidate: &StateCandidate) -> f64 {
+        self.scorer.score(candidate)
+    }
+
+    fn select(&self, candidates: &[StateCandidate], rng: &mut dyn RngCore) -> Option<usize> {
+        let mut scores = candidates
+            .iter()
+            .map(|candidate| self.score(candidate))
+            .enumerate()
+            .collect::<Vec<_>>();
+        scores.sort_by(|(_, left), (_, right)| right.partial_cmp(left).unwrap_or(Ordering::Equal));
+        scores.truncate(self.k.max(1));
+
+        scores.choose(rng).map(|(index, _)| *index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use rand::rngs::StdRng;
+
+    use super::*;
+    use crate::level::parse_level;
+
+    fn candidates(level: &Level) -> Vec<StateCandidate<'_>> {
+        [(4, 10, 2), (6, 8, 1), (5, 12, 3), (2, 20, 1)]
+            .into_iter()
+            .map(|(pushes, moves, box_lines)| StateCandidate {
+                level,
+                player: (1, 1),
+                pushes,
+                moves,
+                box_lines,
+                box_pushes: &[],
+            })
+            .collect()
+    }
+
+    #[test]
+    fn select_best_candidate() {
+        let level = parse_level("####\n#@ #\n####").unwrap();
+        let candidates = candidates(&level);
+        let mut rng = StdRng::seed_from_u64(1);
+        let weighted = WeightedScorer {
+            scorers: vec![(1.0, Arc::new(PushScorer)), (2.0, Arc::new(BoxLineScorer))],
+        };
+
+        assert_eq!(PushScorer.select(&candidates, &mut rng), Some(1));
+        assert_eq!(MoveScorer.select(&candidates, &mut rng), Some(3));
+        assert_eq!(BoxLineScorer.select(&candidates, &mut rng), Some(2));
+        assert_eq!(weighted.score(&candidates[0]), 8.0);
+        assert_eq!(weighted.select(&candidates, &mut rng), Some(2));
+        assert_eq!(PushScorer.select(&[], &mut rng), None);
+    }
+
+    #[test]
+    fn select_among_top_k() {
+        let level = parse_level("####\n#@ #\n####").unwrap();
+        let candidates = candidates(&level);
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut scorer = TopKScorer {
+            scorer: Arc::new(PushScorer),
+            k: 2,
+        };
+
+        let selected = (0..50)
+            .filter_map(|_| scorer.select(&candidates, &mut rng))
+            .collect::<HashSet<_>>();
+        assert_eq!(selected, HashSet::from([1, 2]));
+
+        // k = 0 selects the best candidate like k = 1.
+        scorer.k = 0;
+        assert_eq!(scorer.select(&candidates, &mut rng), Some(1));
+        assert_eq!(scorer.select(&[], &mut rng), None);
+    }
+}
@@ -0,0 +1,83 @@
+//! Serialization of levels with [serde](https://serde.rs).
+//!
+//! Since [`Level`] is an alias for an `ndarray` array, it cannot implement
+//! `Serialize` and `Deserialize` itself. Use this module with the `with`
+//! attribute of serde instead:
+//!
+//! ```text
+//! #[derive(Serialize, Deserialize)]
+//! struct SavedLevel {
+//!     #[serde(with = "sokoban_level_generator::serde_level")]
+//!     level: Level,
+//! }
+//! ```
+//!
+//! A level is serialized as an array of rows, every row is a string
+//! as printed by [`pretty_print_level`](crate::pretty_print_level), except
+//! that empty cells are `~` and special floors `%` (see [`Cell`](crate::Cell)).
+//! Both are not part of the level format, a `_` is a floor as in
+//! [`parse_level`](crate::parse_level).
+//! Rows with spaces outside the level (e.g. from `pretty_print_level`) can be
+//! deserialized as well, floors that are reachable from the edge of the level
+//! become empty cells like in [`parse_level`](crate::parse_level).
+
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serializer};
+
+use crate::cell::Cell;
+use crate::level::{level_from_rows_with, Level};
+
+/// Serialize the level as an array of row strings.
+pub fn serialize<S: Serializer>(level: &Level, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(
+        level
+            .rows()
+            .into_iter()
+            .map(|row| row.iter().map(|cell| cell.to_serde_char()).collect::<String>()),
+    )
+}
+
+/// Deserialize a level from an array of row strings.
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Level, D::Error> {
+    let rows = Vec::<String>::deserialize(deserializer)?;
+    level_from_rows_with(&rows, Cell::from_serde_char).map_err(D::Error::custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Serialize;
+
+    use super::*;
+    use crate::level::{generate_level, parse_level};
+
+    #[derive(Debug, PartialEq, Serialize, serde::Deserialize)]
+    struct SavedLevel {
+        #[serde(with = "crate::serde_level")]
+        level: Level,
+    }
+
+    #[test]
+    fn round_trip_levels() {
+        let mut level = parse_level("  ####\n###  #\n#@$. #\n######").unwrap();
+        level[[2, 4]] = Cell::SpecialFloor;
+        let saved = SavedLevel { level };
+
+        let json = serde_json::to_string(&saved).unwrap();
+
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&json).unwrap(),
+            serde_json::json!({ "level": ["~~####", "###  #", "#@$.%#", "######"] })
+        );
+        assert_eq!(serde_json::from_str::<SavedLevel>(&json).unwrap(), saved);
+
+        let generated = SavedLevel { level: generate_level(2, 2, 1) };
+        let json = serde_json::to_string(&generated).unwrap();
+        assert_eq!(serde_json::from_str::<SavedLevel>(&json).unwrap(), generated);
+
+        let pretty = serde_json::json!({ "level": ["  ####", "###__#", "#@$. #", "######"] });
+        assert_eq!(
+            serde_json::from_value::<SavedLevel>(pretty).unwrap().level,
+            parse_level("  ####\n###  #\n#@$. #\n######").unwrap()
+        );
+    }
+}
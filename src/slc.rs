@@ -0,0 +1,320 @@
+use std::error::Error;
+use std::fmt;
+
+use crate::level::{level_from_rows, pretty_print_level, ParseLevelError};
+use crate::pack::PackEntry;
+
+/// A collection of levels in the SLC (XML) format.
+/// The title of an entry is written as the `Id` of the level and the author
+/// as its `Copyright`. Comments and solutions are not part of the format.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SlcCollection {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub copyright: Option<String>,
+    pub levels: Vec<PackEntry>,
+}
+
+/// Error that occurs when an SLC document cannot be read.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SlcError {
+    /// The document is not well-formed XML.
+    Malformed(String),
+    /// The rows of a level are invalid.
+    InvalidLevel { id: String, error: ParseLevelError },
+}
+
+impl fmt::Display for SlcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Malformed(message) => write!(f, "malformed SLC document: {}", message),
+            Self::InvalidLevel { id, error } => write!(f, "invalid level '{}': {}", id, error),
+        }
+    }
+}
+
+impl Error for SlcError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::InvalidLevel { error, .. } => Some(error),
+            Self::Malformed(_) => None,
+        }
+    }
+}
+
+/// Write the collection as an SLC document. Levels without
+/// a title get their position in the collection as `Id`.
+pub fn write_slc(collection: &SlcCollection) -> String {
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<SokobanLevels>\n");
+
+    if let Some(title) = &collection.title {
+        xml.push_str(&format!("  <Title>{}</Title>\n", escape(title)));
+    }
+    if let Some(description) = &collection.description {
+        xml.push_str(&format!("  <Description>{}</Description>\n", escape(description)));
+    }
+
+    let max_width = collection.levels.iter().map(|entry| entry.level.ncols()).max().unwrap_or(0);
+    let max_height = collection.levels.iter().map(|entry| entry.level.nrows()).max().unwrap_or(0);
+    xml.push_str("  <LevelCollection");
+    if let Some(copyright) = &collection.copyright {
+        xml.push_str(&format!(" Copyright=\"{}\"", escape(copyright)));
+    }
+    xml.push_str(&format!(" MaxWidth=\"{}\" MaxHeight=\"{}\">\n", max_width, max_height));
+
+    for (index, entry) in collection.levels.iter().enumerate() {
+        let id = entry.title.clone().unwrap_or_else(|| (index + 1).to_string());
+        let (height, width) = entry.level.dim();
+        xml.push_str(&format!(
+            "    <Level Id=\"{}\" Width=\"{}\" Height=\"{}\"",
+            escape(&id),
+            width,
+            height
+        ));
+        if let Some(author) = &entry.author {
+            xml.push_str(&format!(" Copyright=\"{}\"", escape(author)));
+        }
+        xml.push_str(">\n");
+
+        for row in pretty_print_level(&entry.level).lines() {
+            xml.push_str(&format!("      <L>{}</L>\n", escape(row.trim_end())));
+        }
+
+        xml.push_str("    </Level>\n");
+    }
+
+    xml.push_str("  </LevelCollection>\n</SokobanLevels>\n");
+    xml
+}
+
+/// Read an SLC document. The `Id` of a level becomes the title
+/// of the entry and the `Copyright` of a level its author.
+pub fn parse_slc(xml: &str) -> Result<SlcCollection, SlcError> {
+    let mut collection = SlcCollection::default();
+    let mut path: Vec<String> = Vec::new();
+    let mut text = String::new();
+    // id, copyright and rows of the current level.
+    let mut level: Option<(Option<String>, Option<String>, Vec<String>)> = None;
+
+    for token in tokenize(xml)? {
+        match token {
+            Token::Text(content) => text.push_str(&content),
+            Token::Start { name, attributes, empty } => {
+                text.clear();
+                match name.as_str() {
+                    "LevelCollection" => collection.copyright = attribute(&attributes, "Copyright"),
+                    "Level" => {
+                        level = Some((
+                            attribute(&attributes, "Id"),
+                            attribute(&attributes, "Copyright"),
+                            Vec::new(),
+                        ))
+                    }
+                    _ => {}
+                }
+                if !empty {
+                    path.push(name);
+                }
+            }
+            Token::End(name) => {
+                if path.pop().as_deref() != Some(name.as_str()) {
+                    return Err(SlcError::Malformed(format!("unexpected closing tag '{}'", name)));
+                }
+
+                let parent = path.last().map(String::as_str);
+                match (name.as_str(), parent, level.as_mut()) {
+                    ("L", Some("Level"), Some((_, _, rows))) => rows.push(text.clone()),
+                    ("Level", _, Some(_)) => {
+                        let (id, author, rows) = level.take().expect("a level was started");
+                        let mut entry = PackEntry::new(level_from_rows(&rows).map_err(|error| {
+                            SlcError::InvalidLevel {
+                                id: id.clone().unwrap_or_default(),
+                                error,
+                            }
+                        })?);
+                        entry.title = id;
+                        entry.author = author;
+                        collection.levels.push(entry);
+                    }
+                    ("Title", Some("SokobanLevels"), _) => collection.title = Some(text.trim().to_string()),
+                    ("Description", Some("SokobanLevels"), _) => {
+                        collection.description = Some(text.trim().to_string())
+                    }
+                    _ => {}
+                }
+                text.clear();
+            }
+        }
+    }
+
+    if let Some(name) = path.pop() {
+        return Err(SlcError::Malformed(format!("unclosed tag '{}'", name)));
+    }
+
+    Ok(collection)
+}
+
+enum Token {
+    Start {
+        name: String,
+        attributes: Vec<(String, String)>,
+        empty: bool,
+    },
+    End(String),
+    Text(String),
+}
+
+/// Split the document into tags and text. Declarations, processing
+/// instructions and comments are skipped.
+fn tokenize(xml: &str) -> Result<Vec<Token>, SlcError> {
+    let mut tokens = Vec::new();
+    let mut rest = xml;
+
+    while !rest.is_empty() {
+        let Some(start) = rest.find('<') else {
+            tokens.push(Token::Text(unescape(rest)?));
+            break;
+        };
+
+        if start > 0 {
+            tokens.push(Token::Text(unescape(&rest[..start])?));
+        }
+        rest = &rest[start..];
+
+        let (end_marker, skip) = if rest.starts_with("<!--") {
+            ("-->", true)
+        } else if rest.starts_with("<?") || rest.starts_with("<!") {
+            (">", true)
+        } else {
+            (">", false)
+        };
+
+        let end = rest
+            .find(end_marker)
+            .ok_or_else(|| SlcError::Malformed("unterminated tag".to_string()))?;
+        let tag = &rest[1..end];
+        rest = &rest[end + end_marker.len()..];
+
+        if skip {
+            continue;
+        }
+
+        if let Some(name) = tag.strip_prefix('/') {
+            tokens.push(Token::End(name.trim().to_string()));
+            continue;
+        }
+
+        let empty = tag.ends_with('/');
+        let tag = tag.trim_end_matches('/');
+        let name_end = tag.find(char::is_whitespace).unwrap_or(tag.len());
+        let name = tag[..name_end].to_string();
+        if name.is_empty() {
+            return Err(SlcError::Malformed("tag without name".to_string()));
+        }
+
+        tokens.push(Token::Start {
+            name,
+            attributes: parse_attributes(&tag[name_end..])?,
+            empty,
+        });
+    }
+
+    Ok(tokens)
+}
+
+fn parse_attributes(mut rest: &str) -> Result<Vec<(String, String)>, SlcError> {
+    let mut attributes = Vec::new();
+
+    loop {
+        rest = rest.trim_start();
+        if rest.is_empty() {
+            return Ok(attributes);
+        }
+
+        let (name, value) = rest
+            .split_once('=')
+            .ok_or_else(|| SlcError::Malformed(format!("attribute without value in '{}'", rest)))?;
+        let value = value.trim_start();
+        let quote = value
+            .chars()
+            .next()
+            .filter(|char| *char == '"' || *char == '\'')
+            .ok_or_else(|| SlcError::Malformed(format!("unquoted attribute '{}'", name.trim())))?;
+        let end = value[1..]
+            .find(quote)
+            .ok_or_else(|| SlcError::Malformed(format!("unterminated attribute '{}'", name.trim())))?;
+
+        attributes.push((name.trim().to_string(), unescape(&value[1..=end])?));
+        rest = &value[end + 2..];
+    }
+}
+
+fn attribute(attributes: &[(String, String)], name: &str) -> Option<String> {
+    attributes
+        .iter()
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value.clone())
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn unescape(text: &str) -> Result<String, SlcError> {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find('&') {
+        result.push_str(&rest[..start]);
+        let end = rest[start..]
+            .find(';')
+            .ok_or_else(|| SlcError::Malformed("unterminated entity".to_string()))?;
+        let entity = &rest[start + 1..start + end];
+        let char = match entity {
+            "amp" => '&',
+            "lt" => '<',
+            "gt" => '>',
+            "quot" => '"',
+            "apos" => '\'',
+            _ => entity
+                .strip_prefix("#x")
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| entity.strip_prefix('#').map(|decimal| decimal.parse()))
+                .and_then(|code| code.ok())
+                .and_then(char::from_u32)
+                .ok_or_else(|| SlcError::Malformed(format!("unknown entity '&{};'", entity)))?,
+        };
+        result.push(char);
+        rest = &rest[start + end + 1..];
+    }
+
+    result.push_str(rest);
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level::parse_level;
+
+    #[test]
+    fn write_and_parse_collection() {
+        let mut entry = PackEntry::new(parse_level("  #####\n###@$.#\n#######").unwrap());
+        entry.author = Some("Jane & John".to_string());
+        let collection = SlcCollection {
+            title: Some("Generated".to_string()),
+            description: None,
+            copyright: Some("smartive".to_string()),
+            levels: vec![entry],
+        };
+
+        let mut result = parse_slc(&write_slc(&collection)).unwrap();
+
+        assert_eq!(result.levels[0].title.take(), Some("1".to_string()));
+        assert_eq!(result, collection);
+    }
+}
@@ -0,0 +1,604 @@
+use std::collections::{HashSet, VecDeque};
+use std::error::Error;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::deadlock::{dead_squares, is_freeze_deadlock};
+use crate::direction::Direction;
+use crate::level::Level;
+
+/// Limits of the solver. The search is aborted when a limit is reached.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SolverLimits {
+    /// Maximum number of states that are explored.
+    pub max_states: usize,
+    /// Maximum duration of the search.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub max_duration: Option<Duration>,
+}
+
+impl SolverLimits {
+    /// Check if the search must be aborted.
+    pub(crate) fn is_reached(&self, states_explored: usize, start: Instant) -> bool {
+        states_explored >= self.max_states
+            || self
+                .max_duration
+                .is_some_and(|duration| states_explored.is_multiple_of(256) && start.elapsed() >= duration)
+    }
+}
+
+impl Default for SolverLimits {
+    fn default() -> Self {
+        Self {
+            max_states: 200_000,
+            max_duration: None,
+        }
+    }
+}
+
+/// The metric that is minimized by the solver.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Metric {
+    /// Find a solution with the least number of pushes.
+    Pushes,
+    /// Find a solution with the least number of player moves.
+    Moves,
+}
+
+/// A single push of a box.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Push {
+    /// Position (y, x) of the box before the push.
+    pub from: (usize, usize),
+    pub direction: Direction,
+}
+
+impl Push {
+    /// Position (y, x) of the box after the push.
+    pub fn to(&self) -> (usize, usize) {
+        let (y, x) = self.from;
+        match self.direction {
+            Direction::Up => (y - 1, x),
+            Direction::Down => (y + 1, x),
+            Direction::Left => (y, x - 1),
+            Direction::Right => (y, x + 1),
+        }
+    }
+}
+
+/// A solution of a level.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Solution {
+    /// The moves of the player in the LURD notation, pushes are uppercase.
+    pub moves: String,
+    /// All pushes of the solution in order.
+    pub pushes: Vec<Push>,
+    /// Number of states that were explored to find the solution.
+    pub states_explored: usize,
+}
+
+impl Solution {
+    pub fn move_count(&self) -> usize {
+        self.moves.len()
+    }
+
+    pub fn push_count(&self) -> usize {
+        self.pushes.len()
+    }
+
+    /// Number of box lines. A box line is a sequence of pushes
+    /// of the same box in the same direction.
+    pub fn box_lines(&self) -> usize {
+        self.pushes
+            .iter()
+            .enumerate()
+            .filter(|(index, push)| {
+                *index == 0 || {
+                    let last = &self.pushes[index - 1];
+                    last.to() != push.from || last.direction != push.direction
+                }
+            })
+            .count()
+    }
+
+    /// Number of box changes. A box change happens when
+    /// the player pushes another box than with the previous push.
+    pub fn box_changes(&self) -> usize {
+        self.pushes
+            .windows(2)
+            .filter(|pushes| pushes[0].to() != pushes[1].from)
+            .count()
+    }
+}
+
+/// Error that occurs when a level cannot be solved.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SolveError {
+    /// The level does not contain a player.
+    NoPlayer,
+    /// The level contains more than one player.
+    MultiplePlayers,
+    /// The level cannot be solved.
+    Unsolvable { states_explored: usize },
+    /// The search was aborted, because the limits were reached.
+    LimitReached { states_explored: usize },
+}
+
+impl SolveError {
+    /// Number of states that were explored before the search ended.
+    pub fn states_explored(&self) -> usize {
+        match self {
+            Self::NoPlayer | Self::MultiplePlayers => 0,
+            Self::Unsolvable { states_explored } | Self::LimitReached { states_explored } => {
+                *states_explored
+            }
+        }
+    }
+}
+
+impl fmt::Display for SolveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoPlayer => write!(f, "the level does not contain a player"),
+            Self::MultiplePlayers => write!(f, "the level contains more than one player"),
+            Self::Unsolvable { states_explored } => {
+                write!(f, "the level is unsolvable ({} states explored)", states_explored)
+            }
+            Self::LimitReached { states_explored } => {
+                write!(f, "the solver limit was reached ({} states explored)", states_explored)
+            }
+        }
+    }
+}
+
+impl Error for SolveError {}
+
+/// Solve the level with a breadth first search. The returned solution is
+/// optimal for the given metric. Pushes onto dead squares and pushes that
+/// freeze a box that is not on a goal are skipped.
+pub fn solve(level: &Level, metric: Metric, limits: &SolverLimits) -> Result<Solution, SolveError> {
+    let (board, state) = Board::from_level(level)?;
+
+    let (steps, states_explored) = match metric {
+        Metric::Pushes => board.search_pushes(&state, limits)?,
+        Metric::Moves => board.search_moves(&state, limits)?,
+    };
+
+    Ok(board.solution(&state, &steps, states_explored))
+}
+
+/// Flat indices of all boxes in the level.
+pub(crate) fn box_indices(level: &Level) -> Vec<usize> {
+    level
+        .iter()
+        .enumerate()
+        .filter(|(_, cell)| cell.is_box())
+        .map(|(index, _)| index)
+        .collect()
+}
+
+/// A move of the player in the LURD notation, the flag is true for pushes.
+pub(crate) type Step = (Direction, bool);
+
+/// Position (y, x) of the only player of the level.
+pub(crate) fn find_player(level: &Level) -> Result<(usize, usize), SolveError> {
+    let mut players = level.indexed_iter().filter(|(_, cell)| cell.is_player());
+    match (players.next(), players.next()) {
+        (Some((player, _)), None) => Ok(player),
+        (None, _) => Err(SolveError::NoPlayer),
+        _ => Err(SolveError::MultiplePlayers),
+    }
+}
+
+/// The static part of a level (walls and goals) with flat indices.
+pub(crate) struct Board {
+    pub(crate) width: usize,
+    pub(crate) height: usize,
+    walls: Vec<bool>,
+    goals: Vec<bool>,
+    dead: Vec<bool>,
+}
+
+/// The dynamic part of a level. The boxes are sorted by their index.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct State {
+    pub(crate) player: usize,
+    pub(crate) boxes: Vec<usize>,
+}
+
+impl Board {
+    pub(crate) fn new(level: &Level) -> Self {
+        let (height, width) = level.dim();
+
+        Self {
+            width,
+            height,
+            walls: level.iter().map(|cell| !cell.is_inside()).collect(),
+            goals: level.iter().map(|cell| cell.is_goal()).collect(),
+            dead: dead_squares(level).iter().copied().collect(),
+        }
+    }
+
+    pub(crate) fn from_level(level: &Level) -> Result<(Self, State), SolveError> {
+        let board = Self::new(level);
+        let player = board.index(find_player(level)?);
+        let boxes = box_indices(level);
+
+        Ok((board, State { player, boxes }))
+    }
+
+    /// Position (y, x) of the flat index.
+    pub(crate) fn position(&self, index: usize) -> (usize, usize) {
+        (index / self.width, index % self.width)
+    }
+
+    pub(crate) fn index(&self, (y, x): (usize, usize)) -> usize {
+        y * self.width + x
+    }
+
+    pub(crate) fn contains(&self, (y, x): (usize, usize)) -> bool {
+        y < self.height && x < self.width
+    }
+
+    pub(crate) fn neighbor(&self, index: usize, direction: Direction) -> Option<usize> {
+        direction
+            .make_move(index % self.width, index / self.width, self.width, self.height)
+            .map(|(x, y)| y * self.width + x)
+    }
+
+    pub(crate) fn is_wall(&self, index: usize) -> bool {
+        self.walls[index]
+    }
+
+    pub(crate) fn is_goal(&self, index: usize) -> bool {
+        self.goals[index]
+    }
+
+    pub(crate) fn is_dead(&self, index: usize) -> bool {
+        self.dead[index]
+    }
+
+    pub(crate) fn is_solved(&self, boxes: &[usize]) -> bool {
+        boxes.iter().all(|&index| self.goals[index])
+    }
+
+    pub(crate) fn box_mask(&self, boxes: &[usize]) -> Vec<bool> {
+        let mut mask = vec![false; self.walls.len()];
+        for &index in boxes {
+            mask[index] = true;
+        }
+        mask
+    }
+
+    /// Calculate all cells the player can walk to without pushing a box.
+    pub(crate) fn reachable(&self, from: usize, boxes: &[bool]) -> Vec<bool> {
+        let mut visited = vec![false; self.walls.len()];
+        let mut stack = vec![from];
+
+        while let Some(index) = stack.pop() {
+            if visited[index] {
+                continue;
+            }
+
+            visited[index] = true;
+
+            for direction in Direction::iterator() {
+                if let Some(next) = self.neighbor(index, direction) {
+                    if !visited[next] && !self.walls[next] && !boxes[next] {
+                        stack.push(next);
+                    }
+                }
+            }
+        }
+
+        visited
+    }
+
+    /// Find the shortest walk of the player without pushing a box.
+    pub(crate) fn walk(&self, from: usize, to: usize, boxes: &[bool]) -> Option<Vec<Direction>> {
+        let mut parents: Vec<Option<(usize, Direction)>> = vec![None; self.walls.len()];
+        let mut visited = vec![false; self.walls.len()];
+        let mut queue = VecDeque::from([from]);
+        visited[from] = true;
+
+        while let Some(index) = queue.pop_front() {
+            if index == to {
+                let mut path = Vec::new();
+                let mut current = to;
+                while let Some((parent, direction)) = parents[current] {
+                    path.push(direction);
+                    current = parent;
+                }
+                path.reverse();
+                return Some(path);
+            }
+
+            for direction in Direction::iterator() {
+                if let Some(next) = self.neighbor(index, direction) {
+                    if !visited[next] && !self.walls[next] && !boxes[next] {
+                        visited[next] = true;
+                        parents[next] = Some((index, direction));
+                        queue.push_back(next);
+                    }
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Breadth first search over the pushes. The player position of a state is
+    /// normalized to the smallest reachable index, so that states that only differ
+    /// in the player position inside the same area are visited once.
+    pub(crate) fn search_pushes(
+        &self,
+        start: &State,
+        limits: &SolverLimits,
+    ) -> Result<(Vec<Step>, usize), SolveError> {
+        struct Node {
+            state: State,
+            parent: usize,
+            push: Option<(usize, Direction)>,
+        }
+
+        let mut boxes = start.boxes.clone();
+        boxes.sort_unstable();
+        let start = State { player: start.player, boxes };
+
+        if self.is_solved(&start.boxes) {
+            return Ok((Vec::new(), 0));
+        }
+
+        let mut seen = HashSet::new();
+        seen.insert(self.normalize(&start));
+        let mut nodes = vec![Node { state: start, parent: 0, push: None }];
+        let mut queue = VecDeque::from([0]);
+        let mut states_explored = 0;
+        let started = Instant::now();
+
+        while let Some(current) = queue.pop_front() {
+            if limits.is_reached(states_explored, started) {
+                return Err(SolveError::LimitReached { states_explored });
+            }
+            states_explored += 1;
+
+            let state = nodes[current].state.clone();
+            let mask = self.box_mask(&state.boxes);
+            let reachable = self.reachable(state.player, &mask);
+
+            for (index, &position) in state.boxes.iter().enumerate() {
+                for direction in Direction::iterator() {
+                    let behind = self.neighbor(position, direction.opposite());
+                    let target = self.neighbor(position, direction);
+                    let (Some(behind), Some(target)) = (behind, target) else {
+                        continue;
+                    };
+
+                    if !reachable[behind] || self.walls[target] || mask[target] || self.dead[target] {
+                        continue;
+                    }
+
+                    let mut boxes = state.boxes.clone();
+                    boxes[index] = target;
+                    boxes.sort_unstable();
+                    if is_freeze_deadlock(self, &self.box_mask(&boxes), target) {
+                        continue;
+                    }
+                    let next = State { player: position, boxes };
+
+                    if !seen.insert(self.normalize(&next)) {
+                        continue;
+                    }
+
+                    let solved = self.is_solved(&next.boxes);
+                    nodes.push(Node { state: next, parent: current, push: Some((position, direction)) });
+
+                    if solved {
+                        let mut pushes = Vec::new();
+                        let mut node = nodes.len() - 1;
+                        while let Some(push) = nodes[node].push {
+                            pushes.push(push);
+                            node = nodes[node].parent;
+                        }
+                        pushes.reverse();
+
+                        let steps = self.pushes_to_steps(&nodes[0].state, &pushes);
+                        return Ok((steps, states_explored));
+                    }
+
+                    queue.push_back(nodes.len() - 1);
+                }
+            }
+        }
+
+        Err(SolveError::Unsolvable { states_explored })
+    }
+
+    /// Breadth first search over the single moves of the player.
+    pub(crate) fn search_moves(
+        &self,
+        start: &State,
+        limits: &SolverLimits,
+    ) -> Result<(Vec<Step>, usize), SolveError> {
+        struct Node {
+            state: State,
+            parent: usize,
+            step: Option<Step>,
+        }
+
+        let mut boxes = start.boxes.clone();
+        boxes.sort_unstable();
+        let start = State { player: start.player, boxes };
+
+        if self.is_solved(&start.boxes) {
+            return Ok((Vec::new(), 0));
+        }
+
+        let mut seen = HashSet::new();
+        seen.insert(start.clone());
+        let mut nodes = vec![Node { state: start, parent: 0, step: None }];
+        let mut queue = VecDeque::from([0]);
+        let mut states_explored = 0;
+        let started = Instant::now();
+
+        while let Some(current) = queue.pop_front() {
+            if limits.is_reached(states_explored, started) {
+                return Err(SolveError::LimitReached { states_explored });
+            }
+            states_explored += 1;
+
+            let state = nodes[current].state.clone();
+
+            for direction in Direction::iterator() {
+                let Some(next_player) = self.neighbor(state.player, direction) else {
+                    continue;
+                };
+                if self.walls[next_player] {
+                    continue;
+                }
+
+                let mut boxes = state.boxes.clone();
+                let push = match boxes.binary_search(&next_player) {
+                    Ok(index) => {
+                        let target = self.neighbor(next_player, direction);
+                        match target {
+                            Some(target)
+                                if !self.walls[target]
+                                    && !self.dead[target]
+                                    && boxes.binary_search(&target).is_err() =>
+                            {
+                                boxes[index] = target;
+                                boxes.sort_unstable();
+                                if is_freeze_deadlock(self, &self.box_mask(&boxes), target) {
+                                    continue;
+                                }
+                                true
+                            }
+                            _ => continue,
+                        }
+                    }
+                    Err(_) => false,
+                };
+
+                let next = State { player: next_player, boxes };
+                if !seen.insert(next.clone()) {
+                    continue;
+                }
+
+                let solved = push && self.is_solved(&next.boxes);
+                nodes.push(Node { state: next, parent: current, step: Some((direction, push)) });
+
+                if solved {
+                    let mut steps = Vec::new();
+                    let mut node = nodes.len() - 1;
+                    while let Some(step) = nodes[node].step {
+                        steps.push(step);
+                        node = nodes[node].parent;
+                    }
+                    steps.reverse();
+
+                    return Ok((steps, states_explored));
+                }
+
+                queue.push_back(nodes.len() - 1);
+            }
+        }
+
+        Err(SolveError::Unsolvable { states_explored })
+    }
+
+    /// Convert a sequence of pushes to the moves of the player,
+    /// including the walks between the pushes.
+    pub(crate) fn pushes_to_steps(&self, start: &State, pushes: &[(usize, Direction)]) -> Vec<Step> {
+        let mut steps = Vec::new();
+        let mut player = start.player;
+        let mut mask = self.box_mask(&start.boxes);
+
+        for &(position, direction) in pushes {
+            let behind = self
+                .neighbor(position, direction.opposite())
+                .expect("the player must stand behind the box");
+            let walk = self
+                .walk(player, behind, &mask)
+                .expect("the player must be able to reach the box");
+            steps.extend(walk.into_iter().map(|direction| (direction, false)));
+            steps.push((direction, true));
+
+            let target = self.neighbor(position, direction).expect("the box must be pushable");
+            mask[position] = false;
+            mask[target] = true;
+            player = position;
+        }
+
+        steps
+    }
+
+    /// Create the solution by replaying the steps from the start state.
+    pub(crate) fn solution(&self, start: &State, steps: &[Step], states_explored: usize) -> Solution {
+        let mut player = start.player;
+        let mut pushes = Vec::new();
+
+        for &(direction, push) in steps {
+            let next = self.neighbor(player, direction).expect("the step must be valid");
+            if push {
+                pushes.push(Push { from: self.position(next), direction });
+            }
+            player = next;
+        }
+
+        Solution {
+            moves: steps.iter().map(|(direction, push)| direction.to_char(*push)).collect(),
+            pushes,
+            states_explored,
+        }
+    }
+
+    /// Normalize the state for the push search: the player is moved
+    /// to the smallest reachable index.
+    fn normalize(&self, state: &State) -> (Vec<usize>, usize) {
+        let reachable = self.reachable(state.player, &self.box_mask(&state.boxes));
+        let player = reachable.iter().position(|&reachable| reachable).unwrap_or(state.player);
+        (state.boxes.clone(), player)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level::parse_level;
+
+    #[test]
+    fn solve_push_optimal() {
+        let level = parse_level("#######\n#@ $ .#\n#######").unwrap();
+
+        let solution = solve(&level, Metric::Pushes, &SolverLimits::default()).unwrap();
+
+        assert_eq!(solution.moves, "rRR");
+        assert_eq!(solution.box_lines(), 1);
+        assert_eq!(solution.box_changes(), 0);
+    }
+
+    #[test]
+    fn solve_move_optimal() {
+        let level = parse_level("######\n#.$ @#\n#.$  #\n######").unwrap();
+
+        let solution = solve(&level, Metric::Moves, &SolverLimits::default()).unwrap();
+
+        assert_eq!(solution.move_count(), 5);
+        assert_eq!(solution.push_count(), 2);
+        assert_eq!(solution.box_changes(), 1);
+    }
+
+    #[test]
+    fn solve_unsolvable_level() {
+        let level = parse_level("######\n#$  .#\n#@   #\n######").unwrap();
+
+        let result = solve(&level, Metric::Pushes, &SolverLimits::default());
+
+        assert!(matches!(result, Err(SolveError::Unsolvable { .. })));
+    }
+}
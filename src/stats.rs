@@ -0,0 +1,91 @@
+/// Statistics of a generation, see [`try_generate_level_with_stats`](crate::try_generate_level_with_stats).
+/// Statistics of many generations can be combined with [`merge`](Self::merge).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GenerationStats {
+    pub attempts: u32,
+    /// Number of generated layouts.
+    pub layouts: u32,
+    /// Layouts that were rejected by the requirements.
+    pub rejections: RequirementRejections,
+    /// Placements of the entities that did not find a start state.
+    pub placement_failures: u32,
+    /// Levels that did not meet the required pushes or difficulty.
+    pub target_rejections: u32,
+    /// Randomly drawn rooms that did not match their surrounding rooms or the constraints.
+    pub template_mismatches: u64,
+    /// Layouts that were abandoned because no drawn room matched the
+    /// surrounding rooms and the constraints.
+    pub constraint_failures: u32,
+    /// Total number of states in the backtrack maps of the reverse searches.
+    pub backtrack_states: u64,
+    /// Number of states in the largest backtrack map.
+    pub max_backtrack_states: usize,
+}
+
+/// Number of layouts that were rejected by each requirement. The requirements
+/// are checked in this order, a layout is only counted for the first one it fails.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RequirementRejections {
+    /// Not enough floors for the boxes, the player and a free floor.
+    pub enough_space: u32,
+    /// Not all floors are connected.
+    pub connectivity: u32,
+    /// Floors with walls on three sides.
+    pub no_surrounded_floors: u32,
+    /// Spaces of 3x4 floors.
+    pub no_large_spaces: u32,
+    /// Not enough cells for the goals.
+    pub enough_goal_places: u32,
+}
+
+impl GenerationStats {
+    /// Add the statistics of another generation.
+    pub fn merge(&mut self, other: &GenerationStats) {
+        self.attempts += other.attempts;
+        self.layouts += other.layouts;
+        self.rejections.enough_space += other.rejections.enough_space;
+        self.rejections.connectivity += other.rejections.connectivity;
+        self.rejections.no_surrounded_floors += other.rejections.no_surrounded_floors;
+        self.rejections.no_large_spaces += other.rejections.no_large_spaces;
+        self.rejections.enough_goal_places += other.rejections.enough_goal_places;
+        self.placement_failures += other.placement_failures;
+        self.target_rejections += other.target_rejections;
+        self.template_mismatches += other.template_mismatches;
+        self.constraint_failures += other.constraint_failures;
+        self.backtrack_states += other.backtrack_states;
+        self.max_backtrack_states = self.max_backtrack_states.max(other.max_backtrack_states);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GenerationConfig;
+    use crate::control::GenerationControl;
+    use crate::level::try_generate_level_with_stats;
+
+    #[test]
+    fn count_rejections() {
+        let mut config = GenerationConfig::new(3, 3, 2);
+        config.seed = Some(3);
+
+        let (level, stats) = try_generate_level_with_stats(&config, &GenerationControl::default());
+        let rejections = stats.rejections;
+        let rejected = rejections.enough_space
+            + rejections.connectivity
+            + rejections.no_surrounded_floors
+            + rejections.no_large_spaces
+            + rejections.enough_goal_places;
+
+        assert!(level.is_ok());
+        assert_eq!(stats.attempts, rejected + stats.placement_failures + stats.target_rejections + 1);
+        assert!(stats.max_backtrack_states > 0);
+
+        let mut total = GenerationStats::default();
+        total.merge(&stats);
+        total.merge(&stats);
+        assert_eq!(total.attempts, 2 * stats.attempts);
+    }
+}
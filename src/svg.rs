@@ -0,0 +1,383 @@
+use std::fmt;
+use std::time::Duration;
+
+use crate::cell::Cell;
+use crate::game::{Game, GameError};
+use crate::level::Level;
+
+/// A colour with red, green and blue components.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+/// Formats the colour in the hex notation of CSS (`#rrggbb`).
+impl fmt::Display for Rgb {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}", self.0, self.1, self.2)
+    }
+}
+
+/// Colours of the parts of a rendered level.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Palette {
+    pub wall: Rgb,
+    pub floor: Rgb,
+    pub goal: Rgb,
+    pub box_on_floor: Rgb,
+    pub box_on_goal: Rgb,
+    pub player: Rgb,
+    /// Colour of the grid lines and coordinates.
+    pub grid: Rgb,
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self {
+            wall: Rgb(0x4a, 0x4e, 0x5a),
+            floor: Rgb(0xe8, 0xe1, 0xcf),
+            goal: Rgb(0xd6, 0x45, 0x45),
+            box_on_floor: Rgb(0xc2, 0x8a, 0x3e),
+            box_on_goal: Rgb(0x4f, 0x9a, 0x4a),
+            player: Rgb(0x2f, 0x6b, 0xd4),
+            grid: Rgb(0x22, 0x22, 0x22),
+        }
+    }
+}
+
+/// Options for the rendering of a level.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RenderOptions {
+    /// Width and height of a cell in pixels.
+    pub tile_size: u32,
+    pub palette: Palette,
+    /// Draw grid lines and the coordinates of the rows and columns.
+    pub grid: bool,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            tile_size: 32,
+            palette: Palette::default(),
+            grid: false,
+        }
+    }
+}
+
+/// Render the level as an SVG image. Empty cells outside of the walls are transparent.
+pub fn render_svg(level: &Level, options: &RenderOptions) -> String {
+    let mut svg = svg_start(level, options);
+
+    svg_board(&mut svg, level, options);
+    for ((y, x), cell) in level.indexed_iter() {
+        if cell.is_box() {
+            svg.push_str(&box_shape(y, x, *cell, options, ""));
+        } else if cell.is_player() {
+            svg.push_str(&player_shape(y, x, options, ""));
+        }
+    }
+    svg_grid(&mut svg, level, options);
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Render a replay of the moves (in the LURD notation) as an animated SVG image.
+/// The player and the boxes are moved with SMIL animations, every move takes
+/// `step` and the image stays at the last move. Fails if the moves are not
+/// possible in the level.
+pub fn render_svg_replay(
+    level: &Level,
+    moves: &str,
+    options: &RenderOptions,
+    step: Duration,
+) -> Result<String, GameError> {
+    let mut game = Game::new(level.clone())?;
+    game.apply_moves(moves)?;
+    game.restart();
+
+    // positions of the player and every box after each move.
+    let mut player = vec![game.player()];
+    let mut boxes = level
+        .indexed_iter()
+        .filter(|(_, cell)| cell.is_box())
+        .map(|(position, _)| vec![position])
+        .collect::<Vec<_>>();
+    while game.redo() {
+        let (previous_y, previous_x) = *player.last().expect("the start position is known");
+        let (y, x) = game.player();
+        for positions in boxes.iter_mut() {
+            let position = *positions.last().expect("the start position is known");
+            positions.push(if position == (y, x) {
+                (2 * y - previous_y, 2 * x - previous_x)
+            } else {
+                position
+            });
+        }
+        player.push((y, x));
+    }
+
+    let size = options.tile_size as f64;
+    let mut svg = svg_start(level, options);
+    svg_board(&mut svg, level, options);
+
+    for positions in &boxes {
+        let (y, x) = positions[0];
+        let animations = [
+            animation("x", positions, step, false, |(y, x)| {
+                (origin(y, x, options).0 as f64 + size * 0.1).to_string()
+            }),
+            animation("y", positions, step, false, |(y, x)| {
+                (origin(y, x, options).1 as f64 + size * 0.1).to_string()
+            }),
+            animation("fill", positions, step, true, |position| {
+                box_shape_fill(level[position], options).to_string()
+            }),
+        ];
+        svg.push_str(&box_shape(y, x, level[[y, x]], options, &animations.concat()));
+    }
+
+    let (y, x) = player[0];
+    let animations = [
+        animation("cx", &player, step, false, |(y, x)| {
+            (origin(y, x, options).0 as f64 + size / 2.0).to_string()
+        }),
+        animation("cy", &player, step, false, |(y, x)| {
+            (origin(y, x, options).1 as f64 + size / 2.0).to_string()
+        }),
+    ];
+    svg.push_str(&player_shape(y, x, options, &animations.concat()));
+
+    svg_grid(&mut svg, level, options);
+    svg.push_str("</svg>\n");
+
+    Ok(svg)
+}
+
+/// SMIL animation of the attribute along the positions, one position per step.
+fn animation(
+    attribute: &str,
+    positions: &[(usize, usize)],
+    step: Duration,
+    discrete: bool,
+    value: impl Fn((usize, usize)) -> String,
+) -> String {
+    let steps = positions.len() - 1;
+    if steps == 0 || positions.iter().all(|position| *position == positions[0]) {
+        return String::new();
+    }
+
+    let values = positions.iter().map(|position| value(*position)).collect::<Vec<_>>();
+    let key_times = (0..=steps)
+        .map(|index| (index as f64 / steps as f64).to_string())
+        .collect::<Vec<_>>();
+
+    format!(
+        "<animate attributeName=\"{}\" dur=\"{}s\" values=\"{}\" keyTimes=\"{}\" calcMode=\"{}\" fill=\"freeze\"/>",
+        attribute,
+        step.as_secs_f64() * steps as f64,
+        values.join(";"),
+        key_times.join(";"),
+        if discrete { "discrete" } else { "linear" }
+    )
+}
+
+/// Offset of the level in the image, the grid needs space for the coordinates.
+pub(crate) fn margin(options: &RenderOptions) -> u32 {
+    if options.grid {
+        options.tile_size / 2
+    } else {
+        0
+    }
+}
+
+/// The opening `svg` tag with the size of the level.
+pub(crate) fn svg_start(level: &Level, options: &RenderOptions) -> String {
+    let (height, width) = level.dim();
+    let size = u64::from(options.tile_size);
+    let width = width as u64 * size + u64::from(margin(options));
+    let height = height as u64 * size + u64::from(margin(options));
+
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">\n",
+        width, height, width, height
+    )
+}
+
+/// Draw the walls, floors and goals of the level, without boxes and the player.
+pub(crate) fn svg_board(svg: &mut String, level: &Level, options: &RenderOptions) {
+    let size = options.tile_size;
+    let palette = &options.palette;
+
+    for ((y, x), cell) in level.indexed_iter() {
+        let (left, top) = origin(y, x, options);
+        let fill = match cell {
+            Cell::Empty => continue,
+            Cell::Wall => palette.wall,
+            _ => palette.floor,
+        };
+        svg.push_str(&format!(
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>\n",
+            left, top, size, size, fill
+        ));
+
+        if cell.is_goal() {
+            svg.push_str(&format!(
+                "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"{}\"/>\n",
+                left as f64 + size as f64 / 2.0,
+                top as f64 + size as f64 / 2.0,
+                size as f64 * 0.2,
+                palette.goal
+            ));
+        }
+    }
+}
+
+/// Draw the grid lines and the coordinates (y of the rows, x of the columns).
+pub(crate) fn svg_grid(svg: &mut String, level: &Level, options: &RenderOptions) {
+    if !options.grid {
+        return;
+    }
+
+    let (height, width) = level.dim();
+    let size = u64::from(options.tile_size);
+    let margin = u64::from(margin(options));
+    let font_size = size as f64 * 0.35;
+
+    svg.push_str(&format!(
+        "<g stroke=\"{}\" stroke-opacity=\"0.3\" stroke-width=\"1\">\n",
+        options.palette.grid
+    ));
+    for x in 0..=width as u64 {
+        let left = margin + x * size;
+        svg.push_str(&format!(
+            "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\"/>\n",
+            left,
+            margin,
+            left,
+            margin + height as u64 * size
+        ));
+    }
+    for y in 0..=height as u64 {
+        let top = margin + y * size;
+        svg.push_str(&format!(
+            "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\"/>\n",
+            margin,
+            top,
+            margin + width as u64 * size,
+            top
+        ));
+    }
+    svg.push_str("</g>\n");
+
+    svg.push_str(&format!(
+        "<g fill=\"{}\" font-family=\"monospace\" font-size=\"{}\" text-anchor=\"middle\" dominant-baseline=\"central\">\n",
+        options.palette.grid, font_size
+    ));
+    for x in 0..width as u64 {
+        svg.push_str(&format!(
+            "<text x=\"{}\" y=\"{}\">{}</text>\n",
+            margin as f64 + (x as f64 + 0.5) * size as f64,
+            margin as f64 / 2.0,
+            x
+        ));
+    }
+    for y in 0..height as u64 {
+        svg.push_str(&format!(
+            "<text x=\"{}\" y=\"{}\">{}</text>\n",
+            margin as f64 / 2.0,
+            margin as f64 + (y as f64 + 0.5) * size as f64,
+            y
+        ));
+    }
+    svg.push_str("</g>\n");
+}
+
+/// A box on the cell. The content (e.g. animations) is placed inside the element.
+pub(crate) fn box_shape(y: usize, x: usize, cell: Cell, options: &RenderOptions, content: &str) -> String {
+    let size = options.tile_size as f64;
+    let (left, top) = origin(y, x, options);
+    let fill = box_shape_fill(cell, options);
+
+    format!(
+        "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" rx=\"{}\" fill=\"{}\">{}</rect>\n",
+        left as f64 + size * 0.1,
+        top as f64 + size * 0.1,
+        size * 0.8,
+        size * 0.8,
+        size * 0.1,
+        fill,
+        content
+    )
+}
+
+fn box_shape_fill(cell: Cell, options: &RenderOptions) -> Rgb {
+    if cell.is_goal() {
+        options.palette.box_on_goal
+    } else {
+        options.palette.box_on_floor
+    }
+}
+
+/// The player on the cell. The content (e.g. animations) is placed inside the element.
+pub(crate) fn player_shape(y: usize, x: usize, options: &RenderOptions, content: &str) -> String {
+    let size = options.tile_size as f64;
+    let (left, top) = origin(y, x, options);
+
+    format!(
+        "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"{}\">{}</circle>\n",
+        left as f64 + size / 2.0,
+        top as f64 + size / 2.0,
+        size * 0.35,
+        options.palette.player,
+        content
+    )
+}
+
+/// Position (x, y) of the top left corner of the cell in the image.
+fn origin(y: usize, x: usize, options: &RenderOptions) -> (u64, u64) {
+    let margin = u64::from(margin(options));
+    let size = u64::from(options.tile_size);
+
+    (margin + x as u64 * size, margin + y as u64 * size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level::parse_level;
+
+    #[test]
+    fn render_level() {
+        let level = parse_level("  #####\n###@$.#\n#######").unwrap();
+
+        let svg = render_svg(&level, &RenderOptions::default());
+
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"224\" height=\"96\""));
+        // 19 walls and floors (without the 2 empty cells) and the box.
+        assert_eq!(svg.matches("<rect").count(), 20);
+        assert_eq!(svg.matches("<circle").count(), 2);
+        assert!(!svg.contains("<text"));
+
+        let options = RenderOptions { tile_size: u32::MAX, grid: true, ..RenderOptions::default() };
+        let svg = render_svg(&level, &options);
+        assert!(svg.contains(&format!("width=\"{}\"", 7 * u64::from(u32::MAX) + u64::from(u32::MAX / 2))));
+    }
+
+    #[test]
+    fn render_replay() {
+        let level = parse_level("#######\n#@ $ .#\n#######").unwrap();
+        let options = RenderOptions::default();
+
+        let svg = render_svg_replay(&level, "rRR", &options, Duration::from_millis(500)).unwrap();
+
+        assert!(svg.contains("<animate attributeName=\"x\" dur=\"1.5s\" values=\"99.2;99.2;131.2;163.2\""));
+        assert!(svg.contains("values=\"#c28a3e;#c28a3e;#c28a3e;#4f9a4a\""));
+        assert_eq!(
+            render_svg_replay(&level, "rRRR", &options, Duration::from_millis(500)),
+            Err(GameError::IllegalMove { index: 3, char: 'R' })
+        );
+    }
+}
@@ -0,0 +1,230 @@
+use std::collections::VecDeque;
+
+use ndarray::Array2;
+
+use crate::cell::Cell;
+use crate::deadlock::dead_squares;
+use crate::direction::Direction;
+use crate::level::Level;
+
+/// Characters and colours of the terminal renderer.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Theme {
+    /// The JSoko Level Format, with `-` for floors inside the walls.
+    #[default]
+    Ascii,
+    /// Box-drawing characters for the walls and symbols for the entities.
+    Unicode,
+    /// Emojis, every cell is two columns wide.
+    Emoji,
+    /// Coloured cells with the 256 colours of ANSI terminals, two columns wide.
+    Ansi,
+}
+
+/// Debug information that is drawn on the free floors of the level.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Overlay {
+    /// Marks the dead squares (see [`dead_squares`]).
+    DeadSquares,
+    /// The number of steps of the player to every cell, in base 36 for
+    /// the single column themes and modulo 100 for the others.
+    Distances,
+}
+
+/// Options for the rendering of a level in the terminal.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TerminalOptions {
+    pub theme: Theme,
+    pub overlay: Option<Overlay>,
+}
+
+/// Render the level for the terminal. Unlike [`pretty_print_level`](crate::pretty_print_level),
+/// the floors inside the walls are distinguishable from the empty cells outside.
+pub fn render_terminal(level: &Level, options: &TerminalOptions) -> String {
+    let overlay = match options.overlay {
+        Some(Overlay::DeadSquares) => dead_squares(level).mapv(|dead| dead.then_some(None)),
+        Some(Overlay::Distances) => step_distances(level).mapv(|distance| distance.map(Some)),
+        None => Array2::from_elem(level.dim(), None),
+    };
+
+    let mut output = String::new();
+    for ((y, x), cell) in level.indexed_iter() {
+        // the overlay is only drawn on floors without entities or goals.
+        let mark = overlay[[y, x]].filter(|_| cell.is_floor());
+
+        match options.theme {
+            Theme::Ascii => output.push(match mark {
+                Some(mark) => overlay_char(mark, 'x'),
+                None if cell.is_floor() => '-',
+                None => cell.to_char(),
+            }),
+            Theme::Unicode => output.push(match mark {
+                Some(mark) => overlay_char(mark, '×'),
+                None => unicode_char(level, y, x),
+            }),
+            Theme::Emoji => output.push_str(&match mark {
+                Some(mark) => overlay_text(mark, "❌"),
+                None => emoji(*cell).to_string(),
+            }),
+            Theme::Ansi => output.push_str(&ansi(*cell, mark)),
+        }
+
+        if x == level.ncols() - 1 {
+            if options.theme == Theme::Ansi {
+                output.push_str("\x1b[0m");
+            }
+            output.push('\n');
+        }
+    }
+
+    output
+}
+
+/// The number of steps of the player to all reachable cells.
+fn step_distances(level: &Level) -> Array2<Option<u32>> {
+    let (height, width) = level.dim();
+    let mut distances = Array2::from_elem(level.dim(), None);
+    let mut queue = level
+        .indexed_iter()
+        .filter(|(_, cell)| cell.is_player())
+        .map(|((y, x), _)| (x, y))
+        .collect::<VecDeque<_>>();
+    for (x, y) in &queue {
+        distances[[*y, *x]] = Some(0);
+    }
+
+    while let Some((x, y)) = queue.pop_front() {
+        let distance = distances[[y, x]].unwrap_or_default();
+
+        for direction in Direction::iterator() {
+            if let Some((next_x, next_y)) = direction.make_move(x, y, width, height) {
+                if level[[next_y, next_x]].is_walkable() && distances[[next_y, next_x]].is_none() {
+                    distances[[next_y, next_x]] = Some(distance + 1);
+                    queue.push_back((next_x, next_y));
+                }
+            }
+        }
+    }
+
+    distances
+}
+
+/// A single character for the mark of the overlay, the distance in base 36.
+fn overlay_char(mark: Option<u32>, dead: char) -> char {
+    match mark {
+        Some(distance) => char::from_digit(distance, 36).unwrap_or('+'),
+        None => dead,
+    }
+}
+
+/// Two columns for the mark of the overlay, the distance modulo 100.
+fn overlay_text(mark: Option<u32>, dead: &str) -> String {
+    match mark {
+        Some(distance) => format!("{:>2}", distance % 100),
+        None => dead.to_string(),
+    }
+}
+
+fn unicode_char(level: &Level, y: usize, x: usize) -> char {
+    match level[[y, x]] {
+        Cell::Empty => ' ',
+        Cell::Floor | Cell::SpecialFloor => '·',
+        Cell::Goal => '○',
+        Cell::Box => '□',
+        Cell::BoxOnGoal => '▣',
+        Cell::Player => '☺',
+        Cell::PlayerOnGoal => '☻',
+        Cell::Wall => {
+            let (height, width) = level.dim();
+            let is_wall = |direction: Direction| {
+                direction
+                    .make_move(x, y, width, height)
+                    .is_some_and(|(x, y)| level[[y, x]] == Cell::Wall)
+            };
+
+            match (
+                is_wall(Direction::Up),
+                is_wall(Direction::Down),
+                is_wall(Direction::Left),
+                is_wall(Direction::Right),
+            ) {
+                (false, false, false, false) => '▪',
+                (true, false, false, false) | (false, true, false, false) | (true, true, false, false) => '│',
+                (false, false, true, false) | (false, false, false, true) | (false, false, true, true) => '─',
+                (false, true, false, true) => '┌',
+                (false, true, true, false) => '┐',
+                (true, false, false, true) => '└',
+                (true, false, true, false) => '┘',
+                (true, true, false, true) => '├',
+                (true, true, true, false) => '┤',
+                (false, true, true, true) => '┬',
+                (true, false, true, true) => '┴',
+                (true, true, true, true) => '┼',
+            }
+        }
+    }
+}
+
+fn emoji(cell: Cell) -> &'static str {
+    match cell {
+        Cell::Empty => "  ",
+        Cell::Wall => "🧱",
+        Cell::Floor | Cell::SpecialFloor => "⬜",
+        Cell::Goal => "🎯",
+        Cell::Box => "📦",
+        Cell::BoxOnGoal => "✅",
+        Cell::Player => "🧍",
+        Cell::PlayerOnGoal => "🙋",
+    }
+}
+
+/// The cell with the background and foreground colour of the 256 ANSI colours.
+fn ansi(cell: Cell, mark: Option<Option<u32>>) -> String {
+    const RESET: &str = "\x1b[0m";
+    let (background, foreground, text) = match (cell, mark) {
+        (Cell::Empty, _) => return format!("{}  ", RESET),
+        (_, Some(None)) => (217, 160, "××".to_string()),
+        (_, Some(Some(distance))) => (223, 244, format!("{:>2}", distance % 100)),
+        (Cell::Wall, _) => (240, 240, "  ".to_string()),
+        (Cell::Floor | Cell::SpecialFloor, _) => (223, 223, "  ".to_string()),
+        (Cell::Goal, _) => (223, 160, "()".to_string()),
+        (Cell::Box, _) => (223, 94, "[]".to_string()),
+        (Cell::BoxOnGoal, _) => (217, 28, "[]".to_string()),
+        (Cell::Player, _) => (223, 27, "@@".to_string()),
+        (Cell::PlayerOnGoal, _) => (217, 27, "@@".to_string()),
+    };
+
+    format!("\x1b[48;5;{}m\x1b[38;5;{}m{}", background, foreground, text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level::parse_level;
+
+    #[test]
+    fn render_themes_with_overlay() {
+        let level = parse_level("  #####\n###@ .#\n#  $  #\n#######").unwrap();
+        let render = |theme, overlay| render_terminal(&level, &TerminalOptions { theme, overlay });
+
+        assert_eq!(
+            render(Theme::Ascii, None),
+            "  #####\n###@-.#\n#--$--#\n#######\n"
+        );
+        assert_eq!(
+            render(Theme::Ascii, Some(Overlay::DeadSquares)),
+            "  #####\n###@-.#\n#xx$xx#\n#######\n"
+        );
+        assert_eq!(
+            render(Theme::Ascii, Some(Overlay::Distances)),
+            "  #####\n###@1.#\n#--$23#\n#######\n"
+        );
+        assert_eq!(
+            render(Theme::Unicode, None),
+            "  ┌───┐\n┌─┘☺·○│\n│··□··│\n└─────┘\n"
+        );
+    }
+}
@@ -0,0 +1,406 @@
+use std::error::Error;
+use std::fmt;
+
+use ndarray::Array2;
+use serde::{Deserialize, Serialize};
+
+use crate::cell::Cell;
+use crate::level::{Level, MAX_LEVEL_SIZE};
+
+/// Flags of flipped tiles in the global tile ids of Tiled.
+const FLIP_FLAGS: u32 = 0xf000_0000;
+
+/// Global tile ids of the cells in a [Tiled](https://www.mapeditor.org) map.
+/// The id `0` means "no tile" in Tiled.
+///
+/// Walls, floors and goals are tiles of the tile layer. Boxes and the player are
+/// tile objects of the object layer, the tile layer contains the floor or goal
+/// below them.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TileIds {
+    /// Cells outside of the walls.
+    pub empty: u32,
+    pub wall: u32,
+    pub floor: u32,
+    pub special_floor: u32,
+    pub goal: u32,
+    pub box_on_floor: u32,
+    pub box_on_goal: u32,
+    pub player_on_floor: u32,
+    pub player_on_goal: u32,
+}
+
+impl TileIds {
+    /// The id of the tile below the entities of the cell.
+    fn ground(&self, cell: Cell) -> u32 {
+        match cell {
+            Cell::Empty => self.empty,
+            Cell::Wall => self.wall,
+            Cell::Floor | Cell::Box | Cell::Player => self.floor,
+            Cell::SpecialFloor => self.special_floor,
+            Cell::Goal | Cell::BoxOnGoal | Cell::PlayerOnGoal => self.goal,
+        }
+    }
+
+    /// The id and the type of the object on the cell, if there is one.
+    fn object(&self, cell: Cell) -> Option<(u32, &'static str)> {
+        match cell {
+            Cell::Box => Some((self.box_on_floor, "box")),
+            Cell::BoxOnGoal => Some((self.box_on_goal, "box")),
+            Cell::Player => Some((self.player_on_floor, "player")),
+            Cell::PlayerOnGoal => Some((self.player_on_goal, "player")),
+            _ => None,
+        }
+    }
+
+    fn cell(&self, gid: u32) -> Option<Cell> {
+        [
+            (self.empty, Cell::Empty),
+            (self.wall, Cell::Wall),
+            (self.floor, Cell::Floor),
+            (self.special_floor, Cell::SpecialFloor),
+            (self.goal, Cell::Goal),
+        ]
+        .into_iter()
+        .find(|(id, _)| *id == gid)
+        .map(|(_, cell)| cell)
+    }
+}
+
+/// Tileset with the tiles in the order wall, floor, goal, box, box on goal,
+/// player and player on goal. Special floors are drawn as floor.
+impl Default for TileIds {
+    fn default() -> Self {
+        Self {
+            empty: 0,
+            wall: 1,
+            floor: 2,
+            special_floor: 2,
+            goal: 3,
+            box_on_floor: 4,
+            box_on_goal: 5,
+            player_on_floor: 6,
+            player_on_goal: 7,
+        }
+    }
+}
+
+/// Options for the export of a level as a Tiled map.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TiledOptions {
+    pub tile_ids: TileIds,
+    /// Width and height of a tile in pixels.
+    pub tile_size: u32,
+    /// Path of the external tileset, relative to the map.
+    pub tileset: String,
+}
+
+impl Default for TiledOptions {
+    fn default() -> Self {
+        Self {
+            tile_ids: TileIds::default(),
+            tile_size: 32,
+            tileset: "sokoban.tsj".to_string(),
+        }
+    }
+}
+
+/// Error that occurs when a Tiled map cannot be read as a level.
+#[derive(Debug)]
+pub enum TiledError {
+    /// The map is not valid JSON or does not have the structure of a Tiled map.
+    Json(serde_json::Error),
+    /// The map does not contain a tile layer with a (not encoded) data array.
+    MissingTileLayer,
+    /// The tile at the index is not mapped to a cell.
+    UnknownTile { index: usize, gid: u32 },
+    /// The object is neither a box nor the player.
+    UnknownObject { id: u32 },
+    /// The object is outside of the map or not on a walkable cell.
+    InvalidObjectPosition { id: u32 },
+    /// The map has more than [`MAX_LEVEL_SIZE`] rows or columns.
+    TooLarge { width: usize, height: usize },
+}
+
+impl fmt::Display for TiledError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Json(error) => write!(f, "invalid Tiled map: {}", error),
+            Self::MissingTileLayer => write!(f, "the map does not contain a tile layer"),
+            Self::UnknownTile { index, gid } => {
+                write!(f, "unknown tile {} at index {}", gid, index)
+            }
+            Self::UnknownObject { id } => write!(f, "object {} is neither a box nor the player", id),
+            Self::InvalidObjectPosition { id } => {
+                write!(f, "object {} is not on a walkable cell", id)
+            }
+            Self::TooLarge { width, height } => write!(
+                f,
+                "the map has {}x{} tiles, but at most {} rows and columns are supported",
+                width, height, MAX_LEVEL_SIZE
+            ),
+        }
+    }
+}
+
+impl Error for TiledError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Json(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Map {
+    #[serde(rename = "type", default)]
+    kind: String,
+    #[serde(default)]
+    version: String,
+    #[serde(default)]
+    orientation: String,
+    #[serde(default)]
+    renderorder: String,
+    #[serde(default)]
+    infinite: bool,
+    width: usize,
+    height: usize,
+    tilewidth: u32,
+    tileheight: u32,
+    #[serde(default)]
+    nextlayerid: u32,
+    #[serde(default)]
+    nextobjectid: u32,
+    layers: Vec<Layer>,
+    #[serde(default)]
+    tilesets: Vec<Tileset>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type")]
+enum Layer {
+    #[serde(rename = "tilelayer")]
+    Tiles {
+        id: u32,
+        name: String,
+        width: usize,
+        height: usize,
+        #[serde(default)]
+        opacity: f64,
+        #[serde(default)]
+        visible: bool,
+        /// Missing for encoded (base64) data.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        data: Option<Vec<u32>>,
+    },
+    #[serde(rename = "objectgroup")]
+    Objects {
+        id: u32,
+        name: String,
+        #[serde(default)]
+        opacity: f64,
+        #[serde(default)]
+        visible: bool,
+        objects: Vec<Object>,
+    },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Object {
+    id: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    gid: Option<u32>,
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    kind: Option<String>,
+    /// The type is called class in Tiled 1.9.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    class: Option<String>,
+    x: f64,
+    y: f64,
+    #[serde(default)]
+    width: f64,
+    #[serde(default)]
+    height: f64,
+    #[serde(default)]
+    visible: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Tileset {
+    firstgid: u32,
+    #[serde(default)]
+    source: String,
+}
+
+/// Write the level as a [Tiled](https://www.mapeditor.org) JSON map with the tile
+/// layer "level" (walls, floors and goals) and the object layer "entities"
+/// (boxes and the player).
+pub fn write_tiled_map(level: &Level, options: &TiledOptions) -> String {
+    let (height, width) = level.dim();
+    let size = options.tile_size;
+    let ids = &options.tile_ids;
+
+    let objects = level
+        .indexed_iter()
+        .filter_map(|((y, x), cell)| {
+            let (gid, kind) = ids.object(*cell)?;
+            Some((y, x, gid, kind))
+        })
+        .enumerate()
+        .map(|(index, (y, x, gid, kind))| Object {
+            id: index as u32 + 1,
+            gid: Some(gid),
+            kind: Some(kind.to_string()),
+            class: None,
+            // tile objects are aligned to their bottom left corner.
+            x: (x as u32 * size) as f64,
+            y: ((y as u32 + 1) * size) as f64,
+            width: size as f64,
+            height: size as f64,
+            visible: true,
+        })
+        .collect::<Vec<_>>();
+
+    let map = Map {
+        kind: "map".to_string(),
+        version: "1.10".to_string(),
+        orientation: "orthogonal".to_string(),
+        renderorder: "right-down".to_string(),
+        infinite: false,
+        width,
+        height,
+        tilewidth: size,
+        tileheight: size,
+        nextlayerid: 3,
+        nextobjectid: objects.len() as u32 + 1,
+        layers: vec![
+            Layer::Tiles {
+                id: 1,
+                name: "level".to_string(),
+                width,
+                height,
+                opacity: 1.0,
+                visible: true,
+                data: Some(level.iter().map(|cell| ids.ground(*cell)).collect()),
+            },
+            Layer::Objects {
+                id: 2,
+                name: "entities".to_string(),
+                opacity: 1.0,
+                visible: true,
+                objects,
+            },
+        ],
+        tilesets: vec![Tileset {
+            firstgid: 1,
+            source: options.tileset.clone(),
+        }],
+    };
+
+    serde_json::to_string_pretty(&map).expect("a map can always be serialized")
+}
+
+/// Read a level from a Tiled JSON map. The cells are read from the first
+/// tile layer, boxes and the player from the objects of all object layers.
+/// Objects are recognized by their tile id or by the type "box" or "player".
+pub fn parse_tiled_map(json: &str, tile_ids: &TileIds) -> Result<Level, TiledError> {
+    let map: Map = serde_json::from_str(json).map_err(TiledError::Json)?;
+    if map.width > MAX_LEVEL_SIZE || map.height > MAX_LEVEL_SIZE {
+        return Err(TiledError::TooLarge {
+            width: map.width,
+            height: map.height,
+        });
+    }
+
+    let data = map
+        .layers
+        .iter()
+        .find_map(|layer| match layer {
+            Layer::Tiles { data, .. } => data.as_ref(),
+            _ => None,
+        })
+        .filter(|data| map.width.checked_mul(map.height) == Some(data.len()))
+        .ok_or(TiledError::MissingTileLayer)?;
+
+    let cells = data
+        .iter()
+        .enumerate()
+        .map(|(index, gid)| {
+            tile_ids
+                .cell(gid & !FLIP_FLAGS)
+                .ok_or(TiledError::UnknownTile { index, gid: *gid })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut level =
+        Array2::from_shape_vec((map.height, map.width), cells).map_err(|_| TiledError::MissingTileLayer)?;
+
+    let objects = map.layers.iter().flat_map(|layer| match layer {
+        Layer::Objects { objects, .. } => objects.as_slice(),
+        _ => &[],
+    });
+
+    for object in objects {
+        let gid = object.gid.map(|gid| gid & !FLIP_FLAGS);
+        let kind = object.kind.as_deref().or(object.class.as_deref());
+        let is_box = gid.is_some_and(|gid| gid == tile_ids.box_on_floor || gid == tile_ids.box_on_goal)
+            || kind == Some("box");
+        let is_player = gid
+            .is_some_and(|gid| gid == tile_ids.player_on_floor || gid == tile_ids.player_on_goal)
+            || kind == Some("player");
+        if !is_box && !is_player {
+            return Err(TiledError::UnknownObject { id: object.id });
+        }
+
+        // tile objects are aligned to their bottom left corner, other objects to the top left.
+        let x = (object.x / map.tilewidth as f64).floor();
+        let y = (object.y / map.tileheight as f64).floor() - if gid.is_some() { 1.0 } else { 0.0 };
+        let position = (y as usize, x as usize);
+
+        match level.get_mut(position) {
+            Some(cell) if x >= 0.0 && y >= 0.0 && cell.is_walkable() => {
+                *cell = if is_box {
+                    cell.with_box()
+                } else {
+                    cell.with_player()
+                };
+            }
+            _ => return Err(TiledError::InvalidObjectPosition { id: object.id }),
+        }
+    }
+
+    Ok(level)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level::parse_level;
+
+    #[test]
+    fn write_and_parse_map() {
+        let level = parse_level("  #####\n###@$.#\n#  $ *#\n#######").unwrap();
+        let options = TiledOptions::default();
+
+        let json = write_tiled_map(&level, &options);
+
+        assert!(json.contains("\"type\": \"objectgroup\""));
+        assert_eq!(parse_tiled_map(&json, &options.tile_ids).unwrap(), level);
+        assert_eq!(level[[0, 0]], Cell::Empty);
+    }
+
+    #[test]
+    fn reject_too_large_maps() {
+        let json = write_tiled_map(&parse_level("###\n#@#\n###").unwrap(), &TiledOptions::default());
+        let huge = json.replacen("\"width\": 3", &format!("\"width\": {}", usize::MAX), 1);
+
+        assert!(matches!(
+            parse_tiled_map(&huge, &TileIds::default()),
+            Err(TiledError::TooLarge { width: usize::MAX, height: 3 })
+        ));
+    }
+}